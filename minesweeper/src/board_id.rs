@@ -0,0 +1,330 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+use crate::difficulty::{MAX_SIZE, MIN_SIZE};
+use crate::generation::{Budget, GenerationError, GenerationMode, SafeArea};
+use crate::mine_field::MineField;
+
+/// Everything needed to rebuild the exact same board:
+/// the seed of the generator, the size of the field,
+/// the number of bombs, the first tile that got digged
+/// and how the bombs got placed.
+///
+/// It can be written and parsed as a string in the format
+/// `<width>x<height>-<bombs>-<x>,<y>-<seed in hex>[-ng][-st|-ns]`,
+/// so that a board can be shared or attached to a bug report.
+/// `ng` stands for the no guess mode, `st` for a safe tile and `ns` for no safety,
+/// when the safe area is missing it's a guaranteed opening.
+/// The ids of fields too small or too big for a `Difficulty`, or whose first click
+/// is outside of the field, are refused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoardId {
+    pub seed: u64,
+    pub width: usize,
+    pub height: usize,
+    pub bombs: usize,
+    pub first_click: (usize, usize),
+    pub mode: GenerationMode,
+    pub safe_area: SafeArea,
+}
+
+impl BoardId {
+    /// Creates a new `BoardId` with a random seed
+    pub fn new(width: usize, height: usize, bombs: usize, first_click: (usize, usize)) -> BoardId {
+        BoardId::with_seed(rand::thread_rng().gen(), width, height, bombs, first_click)
+    }
+
+    /// Creates a new `BoardId` with the specified `seed`
+    pub fn with_seed(
+        seed: u64,
+        width: usize,
+        height: usize,
+        bombs: usize,
+        first_click: (usize, usize),
+    ) -> BoardId {
+        BoardId {
+            seed,
+            width,
+            height,
+            bombs,
+            first_click,
+            mode: GenerationMode::Classic,
+            safe_area: SafeArea::default(),
+        }
+    }
+
+    /// Returns the generator used to place the bombs of this board.
+    ///
+    /// The generator is portable, so the same seed produces the same sequence on every platform.
+    pub fn rng(&self) -> Pcg32 {
+        Pcg32::seed_from_u64(self.seed)
+    }
+
+    /// Creates the `MineField` identified by this `BoardId`.
+    ///
+    /// The no guess mode gives up after a fixed number of attempts, never after some time,
+    /// so the same board is obtained on every machine.
+    pub fn generate(&self) -> Result<MineField, GenerationError> {
+        let mut field = MineField::new(self.width, self.height);
+        let rng = &mut self.rng();
+        match self.mode {
+            GenerationMode::Classic => {
+                field.gen_bombs_with_rng(self.bombs, self.first_click, self.safe_area, rng)?
+            }
+            GenerationMode::NoGuess => {
+                let budget = Budget::attempts(Budget::default().attempts);
+                field.gen_bombs_no_guess(
+                    self.bombs,
+                    self.first_click,
+                    self.safe_area,
+                    rng,
+                    budget,
+                )?;
+            }
+        }
+        Ok(field)
+    }
+
+    /// Creates the `MineField` identified by this `BoardId` without going over `budget`.
+    ///
+    /// When the no guess mode doesn't find a board that can be cleared by logic in time,
+    /// the board is generated in the classic mode instead and `self` is changed to it,
+    /// so that it still identifies the board that has been returned.
+    pub fn generate_within(&mut self, budget: Budget) -> Result<MineField, GenerationError> {
+        if self.mode == GenerationMode::NoGuess {
+            let mut field = MineField::new(self.width, self.height);
+            let rng = &mut self.rng();
+            if field.gen_bombs_no_guess(
+                self.bombs,
+                self.first_click,
+                self.safe_area,
+                rng,
+                budget,
+            )? {
+                return Ok(field);
+            }
+            self.mode = GenerationMode::Classic;
+        }
+        self.generate()
+    }
+}
+
+impl fmt::Display for BoardId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}x{}-{}-{},{}-{:016x}",
+            self.width, self.height, self.bombs, self.first_click.0, self.first_click.1, self.seed
+        )?;
+        if self.mode == GenerationMode::NoGuess {
+            write!(f, "-ng")?;
+        }
+        match self.safe_area {
+            SafeArea::SafeTile => write!(f, "-st"),
+            SafeArea::NoSafety => write!(f, "-ns"),
+            SafeArea::Opening => Ok(()),
+        }
+    }
+}
+
+impl FromStr for BoardId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<BoardId, String> {
+        let err = || format!("'{}' is not a valid board id", s);
+        let parts: Vec<&str> = s.trim().split('-').collect();
+        if parts.len() < 4 {
+            return Err(err());
+        }
+        let mut flags = parts[4..].iter().peekable();
+        let mode = match flags.peek() {
+            Some(&&"ng") => {
+                flags.next();
+                GenerationMode::NoGuess
+            }
+            _ => GenerationMode::Classic,
+        };
+        let safe_area = match flags.next() {
+            None => SafeArea::Opening,
+            Some(&"st") => SafeArea::SafeTile,
+            Some(&"ns") => SafeArea::NoSafety,
+            Some(_) => return Err(err()),
+        };
+        if flags.next().is_some() {
+            return Err(err());
+        }
+        let mut size = parts[0].split('x');
+        let mut click = parts[2].split(',');
+
+        let next = |it: &mut dyn Iterator<Item = &str>| -> Result<usize, String> {
+            it.next().and_then(|v| v.parse().ok()).ok_or_else(err)
+        };
+        let width = next(&mut size)?;
+        let height = next(&mut size)?;
+        let x = next(&mut click)?;
+        let y = next(&mut click)?;
+        let bombs = parts[1].parse().map_err(|_| err())?;
+        let seed = u64::from_str_radix(parts[3], 16).map_err(|_| err())?;
+
+        if size.next().is_some() || click.next().is_some() {
+            return Err(err());
+        }
+        let sizes = MIN_SIZE..=MAX_SIZE;
+        if !sizes.contains(&width) || !sizes.contains(&height) {
+            return Err(format!(
+                "{}, width and height must be between {} and {}",
+                err(),
+                MIN_SIZE,
+                MAX_SIZE
+            ));
+        }
+        if x >= width || y >= height {
+            return Err(format!(
+                "{}, the first click {},{} is outside of the field",
+                err(),
+                x,
+                y
+            ));
+        }
+        let mut id = BoardId::with_seed(seed, width, height, bombs, (x, y));
+        id.mode = mode;
+        id.safe_area = safe_area;
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::solver;
+
+    fn bombs(field: &MineField) -> Vec<(usize, usize)> {
+        let mut bombs = Vec::new();
+        for x in 0..field.width() {
+            for y in 0..field.height() {
+                if field.has_bomb(x, y) {
+                    bombs.push((x, y));
+                }
+            }
+        }
+        bombs
+    }
+
+    #[test]
+    fn display_and_parse_round_trip() {
+        let mut id = BoardId::with_seed(0x0123_4567_89ab_cdef, 30, 16, 99, (4, 7));
+        assert_eq!(id.to_string(), "30x16-99-4,7-0123456789abcdef");
+        assert_eq!(id.to_string().parse(), Ok(id));
+
+        id.mode = GenerationMode::NoGuess;
+        for &safe_area in [SafeArea::NoSafety, SafeArea::SafeTile, SafeArea::Opening].iter() {
+            id.safe_area = safe_area;
+            assert_eq!(id.to_string().parse(), Ok(id));
+        }
+        assert_eq!(id.to_string(), "30x16-99-4,7-0123456789abcdef-ng");
+    }
+
+    #[test]
+    fn invalid_ids_are_refused() {
+        let ids = [
+            "",
+            "30x16-99-4,7",
+            "30x16x2-99-4,7-0",
+            "30-99-4,7-0",
+            "30x16-99-4-0",
+            "30x16-many-4,7-0",
+            "30x16-99-4,7-seed",
+            "30x16-99-4,7-0-st-ng",
+            "30x16-99-4,7-0-ng-st-ns",
+        ];
+        for id in ids.iter() {
+            assert!(id.parse::<BoardId>().is_err(), "{} has been accepted", id);
+        }
+    }
+
+    #[test]
+    fn ids_of_impossible_fields_are_refused() {
+        for id in ["5x16-10-0,0-0", "30x100-99-0,0-0", "0x0-0-0,0-0"].iter() {
+            assert_eq!(
+                id.parse::<BoardId>(),
+                Err(format!(
+                    "'{}' is not a valid board id, width and height must be between 6 and 99",
+                    id
+                ))
+            );
+        }
+        assert!("6x99-10-5,98-0".parse::<BoardId>().is_ok());
+    }
+
+    #[test]
+    fn ids_with_the_first_click_outside_are_refused() {
+        for (id, click) in [("30x16-99-30,7-0", "30,7"), ("30x16-99-4,16-0", "4,16")].iter() {
+            assert_eq!(
+                id.parse::<BoardId>(),
+                Err(format!(
+                    "'{}' is not a valid board id, the first click {} is outside of the field",
+                    id, click
+                ))
+            );
+        }
+        assert!("30x16-99-29,15-0".parse::<BoardId>().is_ok());
+    }
+
+    #[test]
+    fn same_id_gives_same_bombs() {
+        for seed in 0..20 {
+            let id = BoardId::with_seed(seed, 16, 16, 40, (8, 8));
+            let field = id.generate().unwrap();
+            assert_eq!(field.bombs(), 40);
+            assert_eq!(bombs(&field), bombs(&id.generate().unwrap()));
+
+            let parsed: BoardId = id.to_string().parse().unwrap();
+            assert_eq!(bombs(&field), bombs(&parsed.generate().unwrap()));
+        }
+        let first = BoardId::with_seed(1, 16, 16, 40, (8, 8))
+            .generate()
+            .unwrap();
+        let second = BoardId::with_seed(2, 16, 16, 40, (8, 8))
+            .generate()
+            .unwrap();
+        assert_ne!(bombs(&first), bombs(&second));
+    }
+
+    #[test]
+    fn no_guess_board_is_reproducible_and_solvable() {
+        for seed in 0..5 {
+            let mut id = BoardId::with_seed(seed, 16, 16, 40, (3, 12));
+            id.mode = GenerationMode::NoGuess;
+            let field = id.generate().unwrap();
+            assert_eq!(field.bombs(), 40);
+            assert!(solver::is_cleared(&solver::play(&field, id.first_click)));
+            assert_eq!(bombs(&field), bombs(&id.generate().unwrap()));
+
+            // With the same attempts the same board is found, and the id is left as it is
+            let mut within = id;
+            let budget = Budget::attempts(Budget::default().attempts);
+            let field_within = within.generate_within(budget).unwrap();
+            assert_eq!(within, id);
+            assert_eq!(bombs(&field), bombs(&field_within));
+        }
+    }
+
+    #[test]
+    fn no_guess_out_of_time_falls_back_to_classic() {
+        let mut id = BoardId::with_seed(3, 30, 16, 170, (15, 8));
+        id.mode = GenerationMode::NoGuess;
+        let budget = Budget {
+            attempts: 10_000,
+            time: Some(Duration::from_secs(0)),
+        };
+        let field = id.generate_within(budget).unwrap();
+        assert_eq!(id.mode, GenerationMode::Classic);
+        assert_eq!(bombs(&field), bombs(&id.generate().unwrap()));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::generation::SafeArea;
+
+/// Smallest width and height of a field
+pub const MIN_SIZE: usize = 6;
+/// Biggest width and height of a field
+pub const MAX_SIZE: usize = 99;
+
+/// Size of a field and number of bombs hidden inside it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Difficulty {
+    pub width: usize,
+    pub height: usize,
+    pub bombs: usize,
+}
+
+impl Difficulty {
+    /// 9x9 field with 10 bombs
+    pub const BEGINNER: Difficulty = Difficulty {
+        width: 9,
+        height: 9,
+        bombs: 10,
+    };
+    /// 16x16 field with 40 bombs
+    pub const INTERMEDIATE: Difficulty = Difficulty {
+        width: 16,
+        height: 16,
+        bombs: 40,
+    };
+    /// 30x16 field with 99 bombs
+    pub const EXPERT: Difficulty = Difficulty {
+        width: 30,
+        height: 16,
+        bombs: 99,
+    };
+
+    /// Creates a custom `Difficulty`, checking that such a field can be generated
+    pub fn custom(width: usize, height: usize, bombs: usize) -> Result<Difficulty, String> {
+        let sizes = MIN_SIZE..=MAX_SIZE;
+        if !sizes.contains(&width) || !sizes.contains(&height) {
+            return Err(format!(
+                "a {}x{} field is not allowed, width and height must be between {} and {}",
+                width, height, MIN_SIZE, MAX_SIZE
+            ));
+        }
+        let max = Difficulty::max_bombs(width, height);
+        if bombs == 0 || bombs > max {
+            return Err(format!(
+                "a {}x{} field can have between 1 and {} bombs, not {}",
+                width, height, max, bombs
+            ));
+        }
+        Ok(Difficulty {
+            width,
+            height,
+            bombs,
+        })
+    }
+
+    /// Returns the maximum number of bombs that a field of size `width` x `height` can have,
+    /// wherever the first tile gets digged, with the default `SafeArea`
+    pub fn max_bombs(width: usize, height: usize) -> usize {
+        SafeArea::default().max_bombs(width, height)
+    }
+}
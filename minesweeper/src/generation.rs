@@ -0,0 +1,116 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+/// How the bombs get placed on the field:
+/// * `Classic`: the bombs are placed at random
+///
+/// * `NoGuess`: only boards that can be cleared by logic,
+///   starting from the first click, are accepted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GenerationMode {
+    #[default]
+    Classic,
+    NoGuess,
+}
+
+/// Limits how long the no guess generation keeps trying new boards
+#[derive(Clone, Copy, Debug)]
+pub struct Budget {
+    /// Maximum number of boards to try
+    pub attempts: usize,
+    /// Maximum time to spend trying, without it the same generator always gives
+    /// the same board, however fast the machine is
+    pub time: Option<Duration>,
+}
+
+impl Budget {
+    /// Returns a budget limited only by the number of attempts,
+    /// the one used to generate a board from its `BoardId`
+    pub fn attempts(attempts: usize) -> Budget {
+        Budget {
+            attempts,
+            time: None,
+        }
+    }
+}
+
+impl Default for Budget {
+    fn default() -> Budget {
+        Budget {
+            attempts: 10_000,
+            time: Some(Duration::from_secs(3)),
+        }
+    }
+}
+
+/// Area around the first click where no bomb can be placed:
+/// * `NoSafety`: the first click can land on a bomb
+///
+/// * `SafeTile`: the tile of the first click never has a bomb
+///
+/// * `Opening`: neither the tile of the first click nor any of its neighbours
+///   have a bomb, so the first click always digs a zero and opens an area
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SafeArea {
+    NoSafety,
+    SafeTile,
+    #[default]
+    Opening,
+}
+
+impl SafeArea {
+    /// Tells whether or not the tile at `x`, `y` is inside the safe area of a first click at `first`
+    pub fn contains(self, x: usize, y: usize, first: (usize, usize)) -> bool {
+        match self {
+            SafeArea::NoSafety => false,
+            SafeArea::SafeTile => (x, y) == first,
+            SafeArea::Opening => {
+                x + 1 >= first.0 && x <= first.0 + 1 && y + 1 >= first.1 && y <= first.1 + 1
+            }
+        }
+    }
+
+    /// Returns how many tiles of a `width` x `height` field are inside
+    /// the safe area of a first click at `first`
+    pub fn size(self, width: usize, height: usize, first: (usize, usize)) -> usize {
+        let span = |first: usize, len: usize| match self {
+            SafeArea::NoSafety => 0,
+            SafeArea::SafeTile => (first < len) as usize,
+            SafeArea::Opening => (first + 2).min(len).saturating_sub(first.saturating_sub(1)),
+        };
+        span(first.0, width) * span(first.1, height)
+    }
+
+    /// Returns the maximum number of bombs a `width` x `height` field can have,
+    /// wherever the first click lands
+    pub fn max_bombs(self, width: usize, height: usize) -> usize {
+        let span = |len: usize| match self {
+            SafeArea::NoSafety => 0,
+            SafeArea::SafeTile => len.min(1),
+            SafeArea::Opening => len.min(3),
+        };
+        width * height - span(width) * span(height)
+    }
+}
+
+/// Reasons why the bombs can't be placed on a field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GenerationError {
+    /// There are fewer than `bombs` tiles outside the safe area, at most `max` bombs fit
+    TooManyBombs { bombs: usize, max: usize },
+}
+
+impl fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenerationError::TooManyBombs { bombs, max } => write!(
+                f,
+                "can't place {} bombs on the field, there is only room for {}",
+                bombs, max
+            ),
+        }
+    }
+}
+
+impl Error for GenerationError {}
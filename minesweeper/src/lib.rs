@@ -0,0 +1,25 @@
+//! Rules engine of the MineSweeper game.
+//!
+//! This crate has no graphics dependency so that the GUI, bots,
+//! analysers and any other frontend can share the exact same rules.
+
+pub mod board_id;
+pub mod difficulty;
+pub mod generation;
+pub mod mbf;
+pub mod mine_field;
+pub mod probability;
+pub mod records;
+pub mod replay;
+pub mod rmv;
+pub mod save;
+pub mod solver;
+pub mod stats;
+pub use board_id::BoardId;
+pub use difficulty::Difficulty;
+pub use generation::{Budget, GenerationError, GenerationMode, SafeArea};
+pub use mine_field::{Changes, GamePhase, MineField, Outcome, Tile, TileNeighbour, TileState};
+pub use records::{Record, Records, Summary};
+pub use replay::{Action, Replay};
+pub use save::SavedGame;
+pub use stats::{BoardStats, GameStats};
@@ -0,0 +1,124 @@
+//! Minesweeper Board Format (MBF), the binary format used to share mine layouts.
+//!
+//! The file starts with the width and the height of the field (one byte each)
+//! and the number of bombs (two bytes, big endian), followed by the `x` and `y`
+//! of every bomb (one byte each).
+//! Rows are counted from the top of the field, while `MineField` counts them from the bottom,
+//! so the `y` coordinates are flipped while reading and writing.
+
+use std::fs;
+use std::path::Path;
+
+use crate::mine_field::MineField;
+
+/// Reads the field stored in `bytes`
+pub fn read(bytes: &[u8]) -> Result<MineField, String> {
+    let err = |what: &str| format!("invalid MBF board: {}", what);
+    if bytes.len() < 4 {
+        return Err(err("the header is missing"));
+    }
+    let (width, height) = (bytes[0] as usize, bytes[1] as usize);
+    let bombs = u16::from_be_bytes([bytes[2], bytes[3]]) as usize;
+    if bytes.len() != 4 + bombs * 2 {
+        return Err(err(
+            "the number of bombs doesn't match the size of the file",
+        ));
+    }
+    if width == 0 || height == 0 || bombs > width * height {
+        return Err(err(&format!(
+            "{} bombs in a {}x{} field",
+            bombs, width, height
+        )));
+    }
+    let mut field = MineField::new(width, height);
+    for bomb in bytes[4..].chunks(2) {
+        let (x, y) = (bomb[0] as usize, bomb[1] as usize);
+        if x >= width || y >= height {
+            return Err(err(&format!(
+                "the bomb at {},{} is outside the field",
+                x, y
+            )));
+        }
+        if !field.add_bomb_at(x, height - 1 - y) {
+            return Err(err(&format!("there are two bombs at {},{}", x, y)));
+        }
+    }
+    Ok(field)
+}
+
+/// Writes the bombs of `field` in the MBF format
+pub fn write(field: &MineField) -> Result<Vec<u8>, String> {
+    let (width, height) = (field.width(), field.height());
+    if width > u8::MAX as usize || height > u8::MAX as usize {
+        return Err(format!("a {}x{} field is too big for MBF", width, height));
+    }
+    let mut bytes = vec![width as u8, height as u8];
+    bytes.extend_from_slice(&(field.bombs() as u16).to_be_bytes());
+    for y in (0..height).rev() {
+        for x in 0..width {
+            if field.has_bomb(x, y) {
+                bytes.push(x as u8);
+                bytes.push((height - 1 - y) as u8);
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+/// Reads the field stored in the MBF file at `path`
+pub fn load(path: &Path) -> Result<MineField, String> {
+    read(&fs::read(path).map_err(|e| format!("can't load the board: {}", e))?)
+}
+
+/// Writes the bombs of `field` to the MBF file at `path`, replacing it if it exists
+pub fn save(field: &MineField, path: &Path) -> Result<(), String> {
+    fs::write(path, write(field)?).map_err(|e| format!("can't save the board: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_id::BoardId;
+
+    #[test]
+    fn write_and_read_round_trip() {
+        let field = BoardId::with_seed(9, 30, 16, 99, (0, 0))
+            .generate()
+            .unwrap();
+        let bytes = write(&field).unwrap();
+        assert_eq!(&bytes[..4], &[30, 16, 0, 99]);
+        assert_eq!(bytes.len(), 4 + 99 * 2);
+
+        let read = read(&bytes).unwrap();
+        assert_eq!((read.width(), read.height(), read.bombs()), (30, 16, 99));
+        for x in 0..30 {
+            for y in 0..16 {
+                assert_eq!(read.has_bomb(x, y), field.has_bomb(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn reads_a_reference_board() {
+        // 3x2 board with a bomb in the top left and one in the bottom right corner
+        let field = read(&[3, 2, 0, 2, 0, 0, 2, 1]).unwrap();
+        assert_eq!((field.width(), field.height(), field.bombs()), (3, 2, 2));
+        assert!(field.has_bomb(0, 1) && field.has_bomb(2, 0));
+        assert_eq!(write(&field).unwrap(), vec![3, 2, 0, 2, 0, 0, 2, 1]);
+    }
+
+    #[test]
+    fn invalid_boards_are_refused() {
+        let boards: [&[u8]; 5] = [
+            &[3, 2, 0],
+            &[3, 2, 0, 2, 0, 0],
+            &[3, 2, 0, 1, 3, 0],
+            &[3, 2, 0, 2, 1, 1, 1, 1],
+            &[0, 2, 0, 0],
+        ];
+        for bytes in boards.iter() {
+            assert!(read(bytes).is_err(), "{:?} has been accepted", bytes);
+        }
+        assert!(write(&MineField::new(300, 1)).is_err());
+    }
+}
@@ -0,0 +1,970 @@
+use std::time::Instant;
+
+use rand::Rng;
+
+use crate::generation::{Budget, GenerationError, SafeArea};
+use crate::solver;
+
+/// Possible state of any tile:
+/// * `Normal`: an untouched tile
+///
+/// * `Digged`: a tile that has been digged
+///
+/// * `Flagged`: a tile that has a flag on top
+#[derive(PartialEq, Clone, Copy)]
+pub enum TileState {
+    Normal,
+    Digged,
+    Flagged,
+}
+
+#[derive(Clone, Copy)]
+pub enum TileNeighbour {
+    Upper = 0,
+    UpperRight = 1,
+    Right = 2,
+    LowerRight = 3,
+    Lower = 4,
+    LowerLeft = 5,
+    Left = 6,
+    UpperLeft = 7,
+}
+
+/// Phase of the game played on a `MineField`:
+/// * `NotStarted`: no tile has been digged yet
+///
+/// * `Playing`: the game is going on
+///
+/// * `Lost`: a bomb has been digged
+///
+/// * `Won`: every bomb has been flagged and every other tile digged
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GamePhase {
+    NotStarted,
+    Playing,
+    Lost,
+    Won,
+}
+
+/// What happened after a move
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The move had no effect
+    Nothing,
+    /// The flag of the tile at `x`, `y` has been put or removed
+    Flagged(usize, usize),
+    /// Some tiles got digged
+    Revealed(Changes),
+    /// The bomb at `x`, `y` got digged and the game is lost
+    Exploded(usize, usize, Changes),
+    /// The game is won
+    Won(Changes),
+}
+
+impl Outcome {
+    /// Returns the tiles changed by the move
+    pub fn changes(&self) -> &[(usize, usize, bool)] {
+        match self {
+            Outcome::Revealed(changes)
+            | Outcome::Exploded(_, _, changes)
+            | Outcome::Won(changes) => changes,
+            Outcome::Nothing | Outcome::Flagged(..) => &[],
+        }
+    }
+}
+
+/// Tiles changed by digging as `(x, y, digged)`:
+/// `digged` is true for the tiles that got digged and false for the
+/// already digged ones that only need their borders to be updated
+pub type Changes = Vec<(usize, usize, bool)>;
+
+// If the bombs near counter of a tile is 9 means that there is a bomb there
+const BOMB: u8 = 9;
+
+#[derive(Clone)]
+pub struct Tile {
+    state: TileState,
+    near_bombs: u8,
+    near: [bool; 8], // true - digged | false - normal
+}
+
+impl Tile {
+    // Creates a new `Tile`
+    pub fn new() -> Tile {
+        Tile {
+            state: TileState::Normal,
+            near_bombs: 0,
+            near: [false; 8],
+        }
+    }
+
+    /// Tells whether or not the `Tile` has been touched
+    pub fn is_normal(&self) -> bool {
+        self.state == TileState::Normal
+    }
+
+    /// Tells whether or not the `Tile` has been digged
+    pub fn is_digged(&self) -> bool {
+        self.state == TileState::Digged
+    }
+
+    /// Tells whether or not the `Tile` has a flag on top
+    pub fn is_flagged(&self) -> bool {
+        self.state == TileState::Flagged
+    }
+
+    /// Tells whether or not a bomb is hidden inside the `Tile`
+    pub fn has_bomb(&self) -> bool {
+        self.near_bombs == BOMB
+    }
+
+    /// Tells how many adjacent tiles have a bomb hidden inside
+    pub fn near_bombs(&self) -> u8 {
+        self.near_bombs
+    }
+
+    /// Returns the state of the `Tile`
+    pub fn state(&self) -> TileState {
+        self.state
+    }
+
+    /// Puts a bomb in the `Tile`
+    pub fn put_bomb(&mut self) {
+        self.near_bombs = BOMB;
+    }
+
+    /// Digs the `Tile` if it's hidden and returns whether or not it got digged
+    pub fn dig(&mut self) -> bool {
+        if self.is_normal() {
+            self.state = TileState::Digged;
+            true
+        } else {
+            false
+        }
+    }
+
+    // pub fn dig_no_check(&mut self) {
+    //     self.state = TileState::Digged;
+    // }
+
+    /// Puts a flag on the `Tile`
+    pub fn flag(&mut self) -> bool {
+        use TileState::*;
+        self.state = match self.state {
+            Normal => Flagged,
+            Flagged => Normal,
+            Digged => return false,
+        };
+        true
+    }
+
+    /// Set the flag at the `side` specified of the digged adjacent tiles list
+    pub fn neighbour_digged(&mut self, side: TileNeighbour) {
+        self.near[side as usize] = true;
+    }
+
+    /// Tells whether or not the `Tile` on the specified `side` has been digged
+    pub fn is_neighbour_digged(&self, side: TileNeighbour) -> bool {
+        self.near[side as usize]
+    }
+
+    /// Removes the bomb from the `Tile`, `near_bombs` is how many adjacent tiles have a bomb
+    pub fn remove_bomb(&mut self, near_bombs: u8) {
+        self.near_bombs = near_bombs;
+    }
+
+    /// Increments the counter of the bombs planted near the `Tile`
+    pub fn another_bomb_near(&mut self) {
+        if self.near_bombs != BOMB {
+            self.near_bombs += 1;
+        }
+    }
+
+    /// Decrements the counter of the bombs planted near the `Tile`
+    pub fn one_less_bomb_near(&mut self) {
+        if self.near_bombs != BOMB && self.near_bombs > 0 {
+            self.near_bombs -= 1;
+        }
+    }
+
+    #[rustfmt::skip]
+    pub fn border_type(&self) -> u32 {
+        
+        match self.near {
+            // Up | UpR  |   R  | LwR  |  Lw  | LwL  |   L  | UpL //
+            [false, _    , false, _    , false, _    , false, _    ] => {  1 },
+            [false, _    , true , _    , false, _    , false, _    ] => {  2 },
+            [false, _    , false, _    , true , _    , false, _    ] => {  3 },
+            [false, _    , false, _    , false, _    , true , _    ] => {  4 },
+            [true , _    , false, _    , false, _    , false, _    ] => {  5 },
+            [false, _    , false, _    , true , false, true , _    ] => {  6 },
+            [true , _    , false, _    , false, _    , true , false] => {  7 },
+            [true , false, true , _    , false, _    , false, _    ] => {  8 },
+            [false, _    , true , false, true , _    , false, _    ] => {  9 },
+            [true , _    , false, _    , true , _    , false, _    ] => { 10 },
+            [false, _    , true , _    , false, _    , true , _    ] => { 11 },
+            [true , false, true , false, true , _    , false, _    ] => { 12 },
+            [false, _    , true , false, true , false, true , _    ] => { 13 },
+            [true , _    , false, _    , true , false, true , false] => { 14 },
+            [true , false, true , _    , false, _    , true , false] => { 15 },
+            [true , false, true , false, true , false, true , false] => { 16 },
+            [false, _    , false, _    , true , true , true , _    ] => { 17 },
+            [true , _    , false, _    , false, _    , true , true ] => { 18 },
+            [true , true , true , _    , false, _    , false, _    ] => { 19 },
+            [false, _    , true , true , true , _    , false, _    ] => { 20 },
+            [true , true , true , true , true , _    , false, _    ] => { 21 },
+            [true , false, true , true , true , _    , false, _    ] => { 22 },
+            [true , true , true , false, true , _    , false, _    ] => { 23 },
+            [false, _    , true , true , true , true , true , _    ] => { 24 },
+            [false, _    , true , false, true , true , true , _    ] => { 25 },
+            [false, _    , true , true , true , false, true , _    ] => { 26 },
+            [true , _    , false, _    , true , true , true , true ] => { 27 },
+            [true , _    , false, _    , true , false, true , true ] => { 28 },
+            [true , _    , false, _    , true , true , true , false] => { 29 },
+            [true , true , true , _    , false, _    , true , true ] => { 30 },
+            [true , true , true , _    , false, _    , true , false] => { 31 },
+            [true , false, true , _    , false, _    , true , true ] => { 32 },
+            [true , true , true , true , true , true , true , true ] => { 33 },
+            [true , false, true , true , true , true , true , true ] => { 34 },
+            [true , true , true , false, true , true , true , true ] => { 35 },
+            [true , true , true , true , true , false, true , true ] => { 36 },
+            [true , true , true , true , true , true , true , false] => { 37 },
+            [true , false, true , false, true , true , true , true ] => { 38 },
+            [true , true , true , false, true , false, true , true ] => { 39 },
+            [true , true , true , true , true , false, true , false] => { 40 },
+            [true , false, true , true , true , true , true , false] => { 41 },
+            [true , false, true , false, true , false, true , true ] => { 42 },
+            [true , true , true , false, true , false, true , false] => { 43 },
+            [true , false, true , true , true , false, true , false] => { 44 },
+            [true , false, true , false, true , true , true , false] => { 45 },
+            [true , false, true , true , true , false, true , true ] => { 46 },
+            [true , true , true , false, true , true , true , false] => { 47 },
+        }
+    }
+}
+
+impl Default for Tile {
+    fn default() -> Tile {
+        Tile::new()
+    }
+}
+
+#[derive(Clone)]
+pub struct MineField {
+    grid: Vec<Vec<Tile>>,
+    width: usize,
+    height: usize,
+    bombs: usize,
+    phase: GamePhase,
+}
+
+impl MineField {
+    /// Creates a new `MineField` with size: `width` x `height`
+    pub fn new(width: usize, height: usize) -> MineField {
+        MineField {
+            grid: vec![vec![Tile::new(); height]; width],
+            width,
+            height,
+            bombs: 0,
+            phase: GamePhase::NotStarted,
+        }
+    }
+
+    /// Creates a `MineField` from the content of its tiles, given as `tiles[x][y] = (bomb, state)`.
+    ///
+    /// The borders of the digged tiles and the phase of the game are rebuilt from them,
+    /// so that the field is exactly as it was when the tiles got read.
+    pub fn from_tiles(tiles: &[Vec<(bool, TileState)>]) -> MineField {
+        let width = tiles.len();
+        let height = tiles.first().map_or(0, |column| column.len());
+        let mut field = MineField::new(width, height);
+        for (x, column) in tiles.iter().enumerate() {
+            for (y, &(bomb, state)) in column.iter().enumerate() {
+                if bomb {
+                    field.add_bomb_at(x, y);
+                }
+                field.grid[x][y].state = state;
+            }
+        }
+        let mut exploded = false;
+        let mut digged = false;
+        for x in 0..width {
+            for y in 0..height {
+                if !field.is_digged(x, y) {
+                    continue;
+                }
+                digged = true;
+                exploded |= field.has_bomb(x, y);
+                for (xx, yy) in field.neighbours(x, y) {
+                    // Side of the digged tile as seen from its neighbour
+                    use TileNeighbour::*;
+                    let side = match (x + 1 - xx, y + 1 - yy) {
+                        (1, 2) => Upper,
+                        (2, 2) => UpperRight,
+                        (2, 1) => Right,
+                        (2, 0) => LowerRight,
+                        (1, 0) => Lower,
+                        (0, 0) => LowerLeft,
+                        (0, 1) => Left,
+                        _ => UpperLeft,
+                    };
+                    field.grid[xx][yy].neighbour_digged(side);
+                }
+            }
+        }
+        field.phase = if exploded {
+            GamePhase::Lost
+        } else if !digged {
+            GamePhase::NotStarted
+        } else if field.check_win() {
+            GamePhase::Won
+        } else {
+            GamePhase::Playing
+        };
+        field
+    }
+
+    /// Returns the state of the tile at `x`, `y`
+    pub fn state(&self, x: usize, y: usize) -> TileState {
+        self.grid[x][y].state()
+    }
+
+    /// Hides a bomb inside the tile at `x`, `y`
+    pub fn add_bomb_at(&mut self, x: usize, y: usize) -> bool {
+        if !self.has_bomb(x, y) {
+            self.bombs += 1;
+            self.grid[x][y].put_bomb();
+            if x < self.width - 1 {
+                self.grid[x + 1][y].another_bomb_near();
+
+                if y > 0 {
+                    self.grid[x + 1][y - 1].another_bomb_near();
+                }
+                if y < self.height - 1 {
+                    self.grid[x + 1][y + 1].another_bomb_near();
+                }
+            }
+            if x > 0 {
+                self.grid[x - 1][y].another_bomb_near();
+
+                if y > 0 {
+                    self.grid[x - 1][y - 1].another_bomb_near();
+                }
+                if y < self.height - 1 {
+                    self.grid[x - 1][y + 1].another_bomb_near();
+                }
+            }
+            if y > 0 {
+                self.grid[x][y - 1].another_bomb_near();
+            }
+            if y < self.height - 1 {
+                self.grid[x][y + 1].another_bomb_near();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Removes the bomb hidden inside the tile at `x`, `y`
+    pub fn remove_bomb_at(&mut self, x: usize, y: usize) -> bool {
+        if self.has_bomb(x, y) {
+            let near = self.neighbours(x, y);
+            let count = near.iter().filter(|(x, y)| self.has_bomb(*x, *y)).count();
+            self.grid[x][y].remove_bomb(count as u8);
+            self.bombs -= 1;
+            for (x, y) in near {
+                self.grid[x][y].one_less_bomb_near();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Randomly places `number` bombs outside of the `safe` area of the first click at `first`
+    pub fn gen_bombs(
+        &mut self,
+        number: usize,
+        first: (usize, usize),
+        safe: SafeArea,
+    ) -> Result<(), GenerationError> {
+        self.gen_bombs_with_rng(number, first, safe, &mut rand::thread_rng())
+    }
+
+    /// Places `number` bombs outside of the `safe` area of the first click at `first`
+    /// using `rng` to pick the tiles.
+    ///
+    /// The same `rng` state always produces the same board, on every platform.
+    /// Fails, without placing any bomb, if the free tiles outside the safe area are less than `number`.
+    pub fn gen_bombs_with_rng<R: Rng>(
+        &mut self,
+        number: usize,
+        first: (usize, usize),
+        safe: SafeArea,
+        rng: &mut R,
+    ) -> Result<(), GenerationError> {
+        let free = (0..self.width)
+            .flat_map(|x| (0..self.height).map(move |y| (x, y)))
+            .filter(|&(x, y)| !self.has_bomb(x, y) && !safe.contains(x, y, first))
+            .count();
+        if number > free {
+            return Err(GenerationError::TooManyBombs {
+                bombs: number,
+                max: free,
+            });
+        }
+        for _ in 0..number {
+            // Sample 64 bits values so that the sequence doesn't depend on the size of `usize`
+            let mut x = rng.gen_range(0, self.width as u64) as usize;
+            let mut y = rng.gen_range(0, self.height as u64) as usize;
+
+            while self.has_bomb(x, y) || safe.contains(x, y, first) {
+                if x == self.width - 1 {
+                    x = 0;
+                    if y == self.height - 1 {
+                        y = 0;
+                    } else {
+                        y += 1;
+                    }
+                } else {
+                    x += 1;
+                }
+            }
+            self.add_bomb_at(x, y);
+        }
+        Ok(())
+    }
+
+    /// Randomly places `number` bombs outside of the `safe` area of the first click at `first`
+    /// so that the field can be cleared without guessing when starting from `first`.
+    ///
+    /// Boards are generated with `rng` until one can be solved by logic
+    /// or the `budget` runs out, in which case the last board is kept
+    /// and `Ok(false)` is returned.
+    pub fn gen_bombs_no_guess<R: Rng>(
+        &mut self,
+        number: usize,
+        first: (usize, usize),
+        safe: SafeArea,
+        rng: &mut R,
+        budget: Budget,
+    ) -> Result<bool, GenerationError> {
+        let start = Instant::now();
+        let empty = self.clone();
+        self.gen_bombs_with_rng(number, first, safe, rng)?;
+
+        for _ in 0..budget.attempts.max(1) {
+            let played = solver::play(self, first);
+            if solver::is_cleared(&played) {
+                return Ok(true);
+            }
+            if budget.time.is_some_and(|time| start.elapsed() > time) {
+                break;
+            }
+            // Instead of throwing the whole board away try to fix
+            // the spot where the solver got stuck, if that's not possible start over
+            if !self.shift_bomb(&played, first, safe, rng) {
+                *self = empty.clone();
+                self.gen_bombs_with_rng(number, first, safe, rng)?;
+            }
+        }
+        Ok(false)
+    }
+
+    /// Moves one of the bombs near the tiles digged in `played`
+    /// to a tile that has no digged tile around
+    fn shift_bomb<R: Rng>(
+        &mut self,
+        played: &MineField,
+        first: (usize, usize),
+        safe: SafeArea,
+        rng: &mut R,
+    ) -> bool {
+        let mut from = Vec::new();
+        let mut to = Vec::new();
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if !played.is_normal(x, y) {
+                    continue;
+                }
+                let near_digged = self
+                    .neighbours(x, y)
+                    .iter()
+                    .any(|(x, y)| played.is_digged(*x, *y));
+
+                if near_digged && self.has_bomb(x, y) {
+                    from.push((x, y));
+                } else if !near_digged && !self.has_bomb(x, y) && !safe.contains(x, y, first) {
+                    to.push((x, y));
+                }
+            }
+        }
+        if from.is_empty() || to.is_empty() {
+            return false;
+        }
+        let (fx, fy) = from[rng.gen_range(0, from.len() as u64) as usize];
+        let (tx, ty) = to[rng.gen_range(0, to.len() as u64) as usize];
+        self.remove_bomb_at(fx, fy);
+        self.add_bomb_at(tx, ty);
+        true
+    }
+
+    /// Digs the tile at `x`, `y` and, if it has no bombs near, the area around it
+    pub fn dig(&mut self, x: usize, y: usize) -> Outcome {
+        if self.is_over() {
+            return Outcome::Nothing;
+        }
+        let changed = self.dig_tiles(x, y);
+        self.outcome(changed)
+    }
+
+    /// Digs all the neighbours of the digged tile at `x`, `y` that don't have a flag on top,
+    /// but only if the tile has as many flags around as bombs near
+    pub fn chord(&mut self, x: usize, y: usize) -> Outcome {
+        if self.is_over() || !self.is_digged(x, y) {
+            return Outcome::Nothing;
+        }
+        let near = self.neighbours(x, y);
+        let flags = near.iter().filter(|(x, y)| self.is_flagged(*x, *y)).count();
+        if flags != self.bombs_near(x, y) as usize {
+            return Outcome::Nothing;
+        }
+        let mut changed = Vec::new();
+        for (x, y) in near {
+            changed.append(&mut self.dig_tiles(x, y));
+        }
+        self.outcome(changed)
+    }
+
+    /// Puts or removes the flag on top of the tile at `x`, `y`
+    pub fn flag(&mut self, x: usize, y: usize) -> Outcome {
+        if self.is_over() || !self.grid[x][y].flag() {
+            return Outcome::Nothing;
+        }
+        if self.phase == GamePhase::Playing && self.check_win() {
+            self.phase = GamePhase::Won;
+            Outcome::Won(Vec::new())
+        } else {
+            Outcome::Flagged(x, y)
+        }
+    }
+
+    /// Updates the phase of the game after some tiles got digged
+    fn outcome(&mut self, changed: Changes) -> Outcome {
+        if changed.is_empty() {
+            return Outcome::Nothing;
+        }
+        self.phase = GamePhase::Playing;
+        let bomb = changed
+            .iter()
+            .find(|&&(x, y, digged)| digged && self.has_bomb(x, y))
+            .cloned();
+        if let Some((x, y, _)) = bomb {
+            self.phase = GamePhase::Lost;
+            Outcome::Exploded(x, y, changed)
+        } else if self.check_win() {
+            self.phase = GamePhase::Won;
+            Outcome::Won(changed)
+        } else {
+            Outcome::Revealed(changed)
+        }
+    }
+
+    /// Digs the tile at `x`, `y` expanding the hole when there are no bombs near
+    fn dig_tiles(&mut self, x: usize, y: usize) -> Changes {
+        let mut digging = Vec::new();
+        let mut changed = Vec::new();
+        digging.push((x, y));
+        while !digging.is_empty() {
+            // Try to dig the tile
+            if let Some((x, y)) = digging.pop() {
+                let tile = &mut self.grid[x][y];
+                if tile.dig() {
+                    changed.push((x, y, true));
+                    // If this tile is digged
+                    // Expand the hole if it has no bombs
+                    if tile.near_bombs() == 0 {
+                        if x < self.width - 1 {
+                            digging.push((x + 1, y));
+                            if y > 0 {
+                                digging.push((x + 1, y - 1));
+                            }
+                            if y < self.height - 1 {
+                                digging.push((x + 1, y + 1));
+                            }
+                        }
+                        if x > 0 {
+                            digging.push((x - 1, y));
+                            if y > 0 {
+                                digging.push((x - 1, y - 1));
+                            }
+                            if y < self.height - 1 {
+                                digging.push((x - 1, y + 1));
+                            }
+                        }
+                        if y > 0 {
+                            digging.push((x, y - 1));
+                        }
+                        if y < self.height - 1 {
+                            digging.push((x, y + 1));
+                        }
+                    }
+                    if x < self.width - 1 {
+                        if self.is_digged(x + 1, y) {
+                            changed.push((x + 1, y, false));
+                        }
+                        self.grid[x + 1][y].neighbour_digged(TileNeighbour::Left);
+                        if y > 0 {
+                            if self.is_digged(x + 1, y - 1) {
+                                changed.push((x + 1, y - 1, false));
+                            }
+                            self.grid[x + 1][y - 1].neighbour_digged(TileNeighbour::UpperLeft);
+                        }
+                        if y < self.height - 1 {
+                            if self.is_digged(x + 1, y + 1) {
+                                changed.push((x + 1, y + 1, false));
+                            }
+                            self.grid[x + 1][y + 1].neighbour_digged(TileNeighbour::LowerLeft);
+                        }
+                    }
+                    if x > 0 {
+                        if self.is_digged(x - 1, y) {
+                            changed.push((x - 1, y, false));
+                        }
+                        self.grid[x - 1][y].neighbour_digged(TileNeighbour::Right);
+
+                        if y > 0 {
+                            if self.is_digged(x - 1, y - 1) {
+                                changed.push((x - 1, y - 1, false));
+                            }
+                            self.grid[x - 1][y - 1].neighbour_digged(TileNeighbour::UpperRight);
+                        }
+                        if y < self.height - 1 {
+                            if self.is_digged(x - 1, y + 1) {
+                                changed.push((x - 1, y + 1, false));
+                            }
+                            self.grid[x - 1][y + 1].neighbour_digged(TileNeighbour::LowerRight);
+                        }
+                    }
+                    if y > 0 {
+                        if self.is_digged(x, y - 1) {
+                            changed.push((x, y - 1, false));
+                        }
+                        self.grid[x][y - 1].neighbour_digged(TileNeighbour::Upper);
+                    }
+                    if y < self.height - 1 {
+                        if self.is_digged(x, y + 1) {
+                            changed.push((x, y + 1, false));
+                        }
+                        self.grid[x][y + 1].neighbour_digged(TileNeighbour::Lower);
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    pub fn update_all(&mut self) {
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if x < self.width - 1 {
+                    self.grid[x + 1][y].neighbour_digged(TileNeighbour::Left);
+                    if y > 0 {
+                        self.grid[x + 1][y - 1].neighbour_digged(TileNeighbour::UpperLeft);
+                    }
+                    if y < self.height - 1 {
+                        self.grid[x + 1][y + 1].neighbour_digged(TileNeighbour::LowerLeft);
+                    }
+                }
+                if x > 0 {
+                    self.grid[x - 1][y].neighbour_digged(TileNeighbour::Right);
+
+                    if y > 0 {
+                        self.grid[x - 1][y - 1].neighbour_digged(TileNeighbour::UpperRight);
+                    }
+                    if y < self.height - 1 {
+                        self.grid[x - 1][y + 1].neighbour_digged(TileNeighbour::LowerRight);
+                    }
+                }
+                if y > 0 {
+                    self.grid[x][y - 1].neighbour_digged(TileNeighbour::Upper);
+                }
+                if y < self.height - 1 {
+                    self.grid[x][y + 1].neighbour_digged(TileNeighbour::Lower);
+                }
+            }
+        }
+    }
+
+    pub fn check_win(&self) -> bool {
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if self.has_bomb(x, y) && !self.is_flagged(x, y) {
+                    return false;
+                }
+                if !self.has_bomb(x, y) && self.is_normal(x, y) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    pub fn bombs_near(&self, x: usize, y: usize) -> u8 {
+        self.grid[x][y].near_bombs()
+    }
+
+    pub fn border_type(&self, x: usize, y: usize) -> u32 {
+        self.grid[x][y].border_type()
+    }
+
+    pub fn is_normal(&self, x: usize, y: usize) -> bool {
+        self.grid[x][y].is_normal()
+    }
+
+    pub fn is_digged(&self, x: usize, y: usize) -> bool {
+        self.grid[x][y].is_digged()
+    }
+
+    pub fn is_flagged(&self, x: usize, y: usize) -> bool {
+        self.grid[x][y].is_flagged()
+    }
+
+    pub fn has_bomb(&self, x: usize, y: usize) -> bool {
+        self.grid[x][y].has_bomb()
+    }
+
+    /// Returns the phase of the game
+    pub fn phase(&self) -> GamePhase {
+        self.phase
+    }
+
+    /// Tells whether or not the game has been lost or won
+    pub fn is_over(&self) -> bool {
+        self.phase == GamePhase::Lost || self.phase == GamePhase::Won
+    }
+
+    /// Returns the coordinates of the tiles around the one at `x`, `y`
+    pub fn neighbours(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut near = Vec::with_capacity(8);
+        for xx in x.saturating_sub(1)..(x + 2).min(self.width) {
+            for yy in y.saturating_sub(1)..(y + 2).min(self.height) {
+                if xx != x || yy != y {
+                    near.push((xx, yy));
+                }
+            }
+        }
+        near
+    }
+
+    /// Returns how many bombs are hidden in the `MineField`
+    pub fn bombs(&self) -> usize {
+        self.bombs
+    }
+
+    /// Returns the `with` of the `MineField`
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the `height` of the `MineField`
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+#[cfg(test)]
+impl MineField {
+    /// Creates a `MineField` from rows of characters, the first row being the one where `y` is 0:
+    /// `.` is a hidden tile, `*` a hidden bomb, `o` a digged tile, `X` a digged bomb,
+    /// `f` a flag on a tile without a bomb and `F` a flag on a bomb
+    pub(crate) fn from_rows(rows: &[&str]) -> MineField {
+        let width = rows[0].len();
+        let mut tiles = vec![Vec::with_capacity(rows.len()); width];
+        for row in rows.iter() {
+            assert_eq!(row.len(), width, "the rows must have the same length");
+            for (x, c) in row.chars().enumerate() {
+                tiles[x].push(match c {
+                    '.' => (false, TileState::Normal),
+                    '*' => (true, TileState::Normal),
+                    'o' => (false, TileState::Digged),
+                    'X' => (true, TileState::Digged),
+                    'f' => (false, TileState::Flagged),
+                    'F' => (true, TileState::Flagged),
+                    _ => panic!("unknown tile '{}'", c),
+                });
+            }
+        }
+        MineField::from_tiles(&tiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg32;
+
+    use super::*;
+
+    const SIZE: usize = 9;
+    /// The center, a corner and a tile on an edge
+    const CLICKS: [(usize, usize); 3] = [(4, 4), (0, 0), (8, 3)];
+
+    fn generate(bombs: usize, first: (usize, usize), safe: SafeArea, seed: u64) -> MineField {
+        let mut field = MineField::new(SIZE, SIZE);
+        let mut rng = Pcg32::seed_from_u64(seed);
+        field
+            .gen_bombs_with_rng(bombs, first, safe, &mut rng)
+            .unwrap();
+        assert_eq!(field.bombs(), bombs);
+        field
+    }
+
+    #[test]
+    fn safe_tile_is_never_a_bomb() {
+        for &first in CLICKS.iter() {
+            // With this many bombs the first tile is the only one left free
+            let field = generate(SIZE * SIZE - 1, first, SafeArea::SafeTile, 0);
+            assert!(!field.has_bomb(first.0, first.1));
+
+            for seed in 0..100 {
+                let field = generate(40, first, SafeArea::SafeTile, seed);
+                assert!(!field.has_bomb(first.0, first.1));
+            }
+        }
+    }
+
+    #[test]
+    fn opening_digs_a_zero() {
+        for &first in CLICKS.iter() {
+            let max = SafeArea::Opening.size(SIZE, SIZE, first);
+            let field = generate(SIZE * SIZE - max, first, SafeArea::Opening, 0);
+            assert_eq!(field.bombs_near(first.0, first.1), 0);
+
+            for seed in 0..100 {
+                let mut field = generate(40, first, SafeArea::Opening, seed);
+                assert_eq!(field.bombs_near(first.0, first.1), 0);
+                assert!(
+                    field.neighbours(first.0, first.1).len()
+                        < field.dig(first.0, first.1).changes().len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn no_safety_can_put_a_bomb_anywhere() {
+        for &first in CLICKS.iter() {
+            let field = generate(SIZE * SIZE, first, SafeArea::NoSafety, 0);
+            assert!(field.has_bomb(first.0, first.1));
+
+            let exploded = (0..100)
+                .map(|seed| generate(40, first, SafeArea::NoSafety, seed))
+                .any(|field| field.has_bomb(first.0, first.1));
+            assert!(exploded);
+        }
+    }
+
+    #[test]
+    fn too_many_bombs_is_an_error() {
+        let safe_areas = [SafeArea::NoSafety, SafeArea::SafeTile, SafeArea::Opening];
+        for &safe in safe_areas.iter() {
+            for &first in CLICKS.iter() {
+                let max = SIZE * SIZE - safe.size(SIZE, SIZE, first);
+                assert!(max >= safe.max_bombs(SIZE, SIZE));
+
+                let mut field = MineField::new(SIZE, SIZE);
+                let mut rng = Pcg32::seed_from_u64(0);
+                let result = field.gen_bombs_with_rng(max + 1, first, safe, &mut rng);
+                assert_eq!(
+                    result,
+                    Err(GenerationError::TooManyBombs {
+                        bombs: max + 1,
+                        max,
+                    })
+                );
+                assert_eq!(field.bombs(), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn chord_needs_as_many_flags_as_bombs() {
+        let mut field = MineField::from_rows(&["*...", ".o..", "...*"]);
+        assert_eq!(field.chord(1, 1), Outcome::Nothing);
+        assert_eq!(field.chord(0, 1), Outcome::Nothing);
+        assert!(field.is_normal(1, 0));
+
+        field.flag(0, 0);
+        field.flag(2, 0);
+        assert_eq!(field.chord(1, 1), Outcome::Nothing);
+        field.flag(2, 0);
+
+        let outcome = field.chord(1, 1);
+        let digged: Vec<_> = outcome.changes().iter().filter(|c| c.2).collect();
+        // The 7 tiles around, plus the two opened by the zero at (2, 0)
+        assert_eq!(digged.len(), 9);
+        assert!(matches!(outcome, Outcome::Revealed(..)));
+        assert!(field.is_digged(3, 1) && field.is_normal(3, 2));
+        assert_eq!(field.phase(), GamePhase::Playing);
+    }
+
+    #[test]
+    fn chord_on_a_wrong_flag_explodes() {
+        let mut field = MineField::from_rows(&["*f.", ".o.", "..."]);
+        match field.chord(1, 1) {
+            Outcome::Exploded(0, 0, changes) => assert!(changes.contains(&(0, 0, true))),
+            outcome => panic!("{:?} instead of an explosion", outcome),
+        }
+        assert_eq!(field.phase(), GamePhase::Lost);
+        assert!(field.is_flagged(1, 0));
+    }
+
+    #[test]
+    fn phase_goes_from_not_started_to_won() {
+        let mut field = MineField::from_rows(&["*..", "...", "..."]);
+        assert_eq!(field.phase(), GamePhase::NotStarted);
+        // Flags don't start the game
+        assert_eq!(field.flag(1, 1), Outcome::Flagged(1, 1));
+        assert_eq!(field.flag(1, 1), Outcome::Flagged(1, 1));
+        assert_eq!(field.phase(), GamePhase::NotStarted);
+
+        assert!(matches!(field.dig(2, 2), Outcome::Revealed(..)));
+        assert_eq!(field.phase(), GamePhase::Playing);
+        assert!(!field.is_over());
+        // Digging the same tile again does nothing
+        assert_eq!(field.dig(2, 2), Outcome::Nothing);
+
+        assert_eq!(field.flag(0, 0), Outcome::Won(Vec::new()));
+        assert_eq!(field.phase(), GamePhase::Won);
+        assert!(field.is_over());
+    }
+
+    #[test]
+    fn phase_goes_from_playing_to_lost() {
+        let mut field = MineField::from_rows(&["*..", "...", "..."]);
+        field.dig(1, 1);
+        assert_eq!(field.phase(), GamePhase::Playing);
+        assert!(matches!(field.dig(0, 0), Outcome::Exploded(0, 0, _)));
+        assert_eq!(field.phase(), GamePhase::Lost);
+        assert!(field.is_over());
+    }
+
+    #[test]
+    fn moves_are_refused_once_the_game_is_over() {
+        let mut lost = MineField::from_rows(&["X..", ".o.", "..."]);
+        assert_eq!(lost.phase(), GamePhase::Lost);
+        assert_eq!(lost.dig(2, 2), Outcome::Nothing);
+        assert_eq!(lost.flag(2, 2), Outcome::Nothing);
+        assert_eq!(lost.chord(1, 1), Outcome::Nothing);
+        assert!(lost.is_normal(2, 2));
+
+        let mut won = MineField::from_rows(&["Foo", "ooo", "ooo"]);
+        assert_eq!(won.phase(), GamePhase::Won);
+        assert_eq!(won.flag(0, 0), Outcome::Nothing);
+        assert_eq!(won.dig(0, 0), Outcome::Nothing);
+        assert!(won.is_flagged(0, 0));
+        assert_eq!(won.phase(), GamePhase::Won);
+    }
+}
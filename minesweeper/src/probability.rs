@@ -0,0 +1,428 @@
+//! Mine probability of the hidden tiles.
+//!
+//! The hidden tiles next to the digged numbers (the frontier) are split in groups
+//! that don't share any number, then every placement of bombs in a group that agrees
+//! with its numbers is counted.
+//! The groups are finally put together weighting each combination by the number of ways
+//! the bombs left can be placed in the hidden tiles that aren't next to any number.
+
+use crate::mine_field::MineField;
+use crate::solver::{self, Constraint, Knowledge};
+
+/// Maximum number of steps spent counting the placements of a single group,
+/// when it's reached the group is approximated instead
+const ENUMERATION_LIMIT: usize = 1_000_000;
+
+/// Returns the probability of every tile of `field` to have a bomb.
+///
+/// The map is indexed as `map[x][y]` and has the same size of the field.
+/// Digged tiles have a probability of `0.0` and flagged tiles of `1.0`, as flags are trusted.
+pub fn probabilities(field: &MineField) -> Vec<Vec<f64>> {
+    let (w, h) = (field.width(), field.height());
+    let deductions = solver::solve(field);
+    let mut known = vec![vec![Knowledge::Unknown; h]; w];
+    for &(x, y) in deductions.safe.iter() {
+        known[x][y] = Knowledge::Safe;
+    }
+    for &(x, y) in deductions.bombs.iter() {
+        known[x][y] = Knowledge::Bomb;
+    }
+
+    let mut map = vec![vec![0.0; h]; w];
+    let mut left = field.bombs() as isize;
+    for x in 0..w {
+        for y in 0..h {
+            if field.is_flagged(x, y) || known[x][y] == Knowledge::Bomb {
+                map[x][y] = 1.0;
+                left -= 1;
+            }
+        }
+    }
+
+    let constraints = solver::constraints(field, &known);
+    let groups = groups(field, &constraints);
+
+    let mut in_frontier = vec![vec![false; h]; w];
+    for &(x, y) in groups.iter().flat_map(|g| g.tiles.iter()) {
+        in_frontier[x][y] = true;
+    }
+    let mut others = Vec::new();
+    for x in 0..w {
+        for y in 0..h {
+            if field.is_normal(x, y) && known[x][y] == Knowledge::Unknown && !in_frontier[x][y] {
+                others.push((x, y));
+            }
+        }
+    }
+
+    // prefix[i] and suffix[i] are the distributions of the bombs
+    // in the groups before i and from i onwards
+    let mut prefix = vec![vec![1.0]];
+    for g in groups.iter() {
+        let last = convolve(prefix.last().unwrap(), &g.counts);
+        prefix.push(last);
+    }
+    let mut suffix = vec![vec![1.0]];
+    for g in groups.iter().rev() {
+        let last = convolve(suffix.last().unwrap(), &g.counts);
+        suffix.push(last);
+    }
+    suffix.reverse();
+    let all = &prefix[groups.len()];
+
+    // The weight of putting `s` bombs in the frontier is the number of ways
+    // the remaining ones can be placed in the other tiles
+    let mut weights = binomials(others.len(), left, all.len());
+    let mut total = dot(all, &weights, 0);
+    if total == 0.0 {
+        // The field contradicts itself, forget about the bombs left
+        weights = vec![1.0; all.len()];
+        total = dot(all, &weights, 0);
+    }
+
+    for (i, g) in groups.iter().enumerate() {
+        let rest = convolve(&prefix[i], &suffix[i + 1]);
+        let chances: Vec<f64> = (0..g.counts.len())
+            .map(|k| dot(&rest, &weights, k))
+            .collect();
+        for (t, &(x, y)) in g.tiles.iter().enumerate() {
+            let sum: f64 = (0..g.counts.len())
+                .map(|k| g.tile_counts[k][t] * chances[k])
+                .sum();
+            map[x][y] = sum / total;
+        }
+    }
+    if !others.is_empty() {
+        let n = others.len() as isize;
+        let sum: f64 = all
+            .iter()
+            .enumerate()
+            .map(|(s, &c)| c * weights[s] * (left - s as isize).max(0).min(n) as f64)
+            .sum();
+        let p = sum / total / n as f64;
+        for (x, y) in others {
+            map[x][y] = p;
+        }
+    }
+    map
+}
+
+/// A set of frontier tiles together with the numbers next to them
+struct Group {
+    tiles: Vec<(usize, usize)>,
+    /// `counts[k]` is the (relative) number of placements with `k` bombs
+    counts: Vec<f64>,
+    /// `tile_counts[k][t]` is how many of the placements with `k` bombs have one in the tile `t`
+    tile_counts: Vec<Vec<f64>>,
+}
+
+impl Group {
+    /// Counts the placements of bombs in `tiles` that agree with the `constraints`,
+    /// which are given as the number of bombs and the indices of their tiles
+    fn count(tiles: Vec<(usize, usize)>, constraints: Vec<(usize, Vec<usize>)>) -> Group {
+        let n = tiles.len();
+        let mut owners = vec![Vec::new(); n];
+        for (i, (_, c)) in constraints.iter().enumerate() {
+            for &t in c.iter() {
+                owners[t].push(i);
+            }
+        }
+        let mut enumeration = Enumeration {
+            owners,
+            need: constraints.iter().map(|c| c.0).collect(),
+            placed: vec![0; constraints.len()],
+            free: constraints.iter().map(|c| c.1.len()).collect(),
+            bombs: vec![false; n],
+            counts: vec![0.0; n + 1],
+            tile_counts: vec![vec![0.0; n]; n + 1],
+            steps: 0,
+        };
+        let complete = enumeration.place(0);
+        let max = enumeration.counts.iter().cloned().fold(0.0, f64::max);
+
+        if !complete || max == 0.0 {
+            return Group::approximate(tiles, &constraints);
+        }
+        // Only the ratios matter, keeping the numbers small avoids overflows
+        let counts = enumeration.counts.iter().map(|c| c / max).collect();
+        let tile_counts = enumeration
+            .tile_counts
+            .iter()
+            .map(|k| k.iter().map(|c| c / max).collect())
+            .collect();
+        Group {
+            tiles,
+            counts,
+            tile_counts,
+        }
+    }
+
+    /// Gives to each tile the average density of bombs of the numbers next to it,
+    /// and to the group the number of bombs closest to their sum
+    fn approximate(tiles: Vec<(usize, usize)>, constraints: &[(usize, Vec<usize>)]) -> Group {
+        let n = tiles.len();
+        let mut density = vec![0.0; n];
+        let mut owners = vec![0; n];
+        for (bombs, c) in constraints.iter() {
+            for &t in c.iter() {
+                density[t] += *bombs as f64 / c.len() as f64;
+                owners[t] += 1;
+            }
+        }
+        let probabilities: Vec<f64> = density
+            .iter()
+            .zip(owners.iter())
+            .map(|(&d, &o)| d / o.max(1) as f64)
+            .collect();
+        let k = (probabilities.iter().sum::<f64>().round() as usize).min(n);
+
+        let mut counts = vec![0.0; n + 1];
+        let mut tile_counts = vec![vec![0.0; n]; n + 1];
+        counts[k] = 1.0;
+        tile_counts[k] = probabilities;
+        Group {
+            tiles,
+            counts,
+            tile_counts,
+        }
+    }
+}
+
+/// State of the backtracking that counts the placements of a group
+struct Enumeration {
+    /// For each tile the constraints that contain it
+    owners: Vec<Vec<usize>>,
+    /// For each constraint the number of bombs it needs
+    need: Vec<usize>,
+    /// For each constraint the number of bombs placed in its tiles
+    placed: Vec<usize>,
+    /// For each constraint the number of its tiles that are still to be decided
+    free: Vec<usize>,
+    bombs: Vec<bool>,
+    counts: Vec<f64>,
+    tile_counts: Vec<Vec<f64>>,
+    steps: usize,
+}
+
+impl Enumeration {
+    /// Tries both contents for the tile `t` and the following ones,
+    /// returns false if it ran out of steps
+    fn place(&mut self, t: usize) -> bool {
+        if t == self.bombs.len() {
+            let k = self.bombs.iter().filter(|&&b| b).count();
+            self.counts[k] += 1.0;
+            for (i, _) in self.bombs.iter().enumerate().filter(|(_, &b)| b) {
+                self.tile_counts[k][i] += 1.0;
+            }
+            return true;
+        }
+        self.steps += 1;
+        if self.steps > ENUMERATION_LIMIT {
+            return false;
+        }
+        for &bomb in [false, true].iter() {
+            self.bombs[t] = bomb;
+            let mut valid = true;
+            for &c in self.owners[t].iter() {
+                self.free[c] -= 1;
+                self.placed[c] += bomb as usize;
+                valid &=
+                    self.placed[c] <= self.need[c] && self.placed[c] + self.free[c] >= self.need[c];
+            }
+            let complete = !valid || self.place(t + 1);
+            for &c in self.owners[t].iter() {
+                self.free[c] += 1;
+                self.placed[c] -= bomb as usize;
+            }
+            self.bombs[t] = false;
+            if !complete {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Splits the tiles of the `constraints` in groups that don't share any constraint
+/// and counts the placements of each of them.
+///
+/// Tiles are listed in the order they are reached from one constraint to the next,
+/// so that the ones close to each other are decided one after the other.
+fn groups(field: &MineField, constraints: &[Constraint]) -> Vec<Group> {
+    let mut owners = vec![vec![Vec::new(); field.height()]; field.width()];
+    for (i, c) in constraints.iter().enumerate() {
+        for &(x, y) in c.tiles.iter() {
+            owners[x][y].push(i);
+        }
+    }
+    let mut index = vec![vec![None; field.height()]; field.width()];
+    let mut visited = vec![false; constraints.len()];
+    let mut groups = Vec::new();
+
+    for first in 0..constraints.len() {
+        if visited[first] {
+            continue;
+        }
+        visited[first] = true;
+        let mut queue = vec![first];
+        let mut tiles = Vec::new();
+        let mut next = 0;
+        while next < queue.len() {
+            let c = &constraints[queue[next]];
+            next += 1;
+            for &(x, y) in c.tiles.iter() {
+                if index[x][y].is_some() {
+                    continue;
+                }
+                index[x][y] = Some(tiles.len());
+                tiles.push((x, y));
+                for &o in owners[x][y].iter() {
+                    if !visited[o] {
+                        visited[o] = true;
+                        queue.push(o);
+                    }
+                }
+            }
+        }
+        let local = queue
+            .into_iter()
+            .map(|i| {
+                let c = &constraints[i];
+                let tiles = c.tiles.iter().map(|&(x, y)| index[x][y].unwrap());
+                (c.bombs, tiles.collect())
+            })
+            .collect();
+        groups.push(Group::count(tiles, local));
+    }
+    groups
+}
+
+/// Returns the distribution of the sum of two independent numbers of bombs
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    result
+}
+
+/// Returns the sum of `counts[s] * weights[s + offset]`
+fn dot(counts: &[f64], weights: &[f64], offset: usize) -> f64 {
+    counts
+        .iter()
+        .zip(weights.iter().skip(offset))
+        .map(|(c, w)| c * w)
+        .sum()
+}
+
+/// Returns, for every `s` in `0..len`, the number of ways `left - s` bombs
+/// can be placed in `n` tiles, relative to the biggest of them
+fn binomials(n: usize, left: isize, len: usize) -> Vec<f64> {
+    // ln(i!) for every i up to n
+    let mut ln_factorials = vec![0.0; n + 1];
+    for i in 1..=n {
+        ln_factorials[i] = ln_factorials[i - 1] + (i as f64).ln();
+    }
+    let ln_binomials: Vec<Option<f64>> = (0..len)
+        .map(|s| {
+            let k = left - s as isize;
+            if k < 0 || k as usize > n {
+                None
+            } else {
+                let k = k as usize;
+                Some(ln_factorials[n] - ln_factorials[k] - ln_factorials[n - k])
+            }
+        })
+        .collect();
+    let max = ln_binomials
+        .iter()
+        .filter_map(|&b| b)
+        .fold(f64::NEG_INFINITY, f64::max);
+    ln_binomials
+        .into_iter()
+        .map(|b| b.map_or(0.0, |b| (b - max).exp()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_id::BoardId;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} is not {}", a, b);
+    }
+
+    /// Probability of each tile of a group with `k` bombs
+    fn chances(group: &Group, k: usize) -> Vec<f64> {
+        let count = group.counts[k];
+        group.tile_counts[k].iter().map(|c| c / count).collect()
+    }
+
+    #[test]
+    fn isolated_pair_is_a_coin_flip() {
+        let field = MineField::from_rows(&["*oo", ".oo"]);
+        let map = probabilities(&field);
+        assert_close(map[0][0], 0.5);
+        assert_close(map[0][1], 0.5);
+        assert_close(map[1][0], 0.0);
+        assert_close(map[2][1], 0.0);
+    }
+
+    #[test]
+    fn other_tiles_are_weighted_by_the_bombs_left() {
+        // The numbers allow a bomb in the middle or two on the sides, with two bombs in all
+        // the first leaves one bomb for the 3 tiles on the right and the second none,
+        // so the first is 3 times as likely
+        let field = MineField::from_rows(&[".o*o..*."]);
+        let map = probabilities(&field);
+        let expected = [0.25, 0.0, 0.75, 0.0, 0.25, 0.25, 0.25, 0.25];
+        for (x, &p) in expected.iter().enumerate() {
+            assert_close(map[x][0], p);
+        }
+    }
+
+    #[test]
+    fn probabilities_add_up_to_the_bombs() {
+        for seed in 0..10 {
+            let id = BoardId::with_seed(seed, 16, 16, 40, (8, 8));
+            let field = solver::play(&id.generate().unwrap(), id.first_click);
+            let map = probabilities(&field);
+            assert_eq!(map.len(), field.width());
+            assert!(map.iter().all(|column| column.len() == field.height()));
+
+            let sum: f64 = map.iter().flatten().sum();
+            assert!((sum - 40.0).abs() < 1e-6, "{} bombs instead of 40", sum);
+            assert!(map.iter().flatten().all(|&p| (0.0..=1.0).contains(&p)));
+        }
+    }
+
+    #[test]
+    fn small_groups_are_counted_exactly() {
+        // 3 bombs in 10 tiles, one of which in the first 3
+        let constraints = vec![(3, (0..10).collect()), (1, vec![0, 1, 2])];
+        let group = Group::count(vec![(0, 0); 10], constraints);
+        let chances = chances(&group, 3);
+        assert_close(chances[0], 1.0 / 3.0);
+        assert_close(chances[3], 2.0 / 7.0);
+        assert!(group
+            .counts
+            .iter()
+            .enumerate()
+            .all(|(k, &c)| k == 3 || c == 0.0));
+    }
+
+    #[test]
+    fn big_groups_are_approximated() {
+        // There are billions of ways to put 20 bombs in 40 tiles,
+        // more than the steps the enumeration is allowed to take
+        let constraints = vec![(20, (0..40).collect()), (1, vec![0, 1, 2])];
+        let group = Group::count(vec![(0, 0); 40], constraints);
+        let chances = chances(&group, 20);
+        // The tiles get the average density of their numbers
+        assert_close(chances[0], (0.5 + 1.0 / 3.0) / 2.0);
+        assert_close(chances[3], 0.5);
+    }
+}
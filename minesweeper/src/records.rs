@@ -0,0 +1,478 @@
+//! Records of the finished games, used for the best times and the statistics of the player.
+//!
+//! The records are kept in a text file made of a header with the version of the format
+//! followed by one line for each game, in the order they have been played:
+//! ```text
+//! MineSweeper records 2
+//! 9x9-10 classic opening won 12345 17 1760000000
+//! 30x16-99 ng st lost 5021 143 1760000100
+//! ```
+//! Each line has the size of the field and its bombs, the generation mode (`classic` or `ng`),
+//! the safe area of the first click (`opening`, `st` for a safe tile or `ns` for no safety),
+//! the result, the time in milliseconds, the 3BV of the board and the date
+//! in seconds since the Unix epoch. The lines of version 1 have no safe area,
+//! their games all began with an opening.
+//!
+//! The games are told apart by their board parameters, so that the statistics of
+//! a difficulty never mix with the ones of another size, of the no guess mode
+//! or of another safe area.
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::difficulty::Difficulty;
+use crate::generation::{GenerationMode, SafeArea};
+
+/// Version of the records format written by this crate
+pub const RECORDS_VERSION: u32 = 2;
+
+const HEADER: &str = "MineSweeper records";
+
+/// A finished game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Record {
+    pub difficulty: Difficulty,
+    pub mode: GenerationMode,
+    pub safe_area: SafeArea,
+    pub won: bool,
+    /// Time from the first dig to the end of the game
+    pub time: Duration,
+    /// 3BV of the board
+    pub bbbv: usize,
+    /// Seconds since the Unix epoch when the game ended
+    pub date: u64,
+}
+
+impl Record {
+    /// Creates the record of a game that has just ended
+    pub fn now(
+        difficulty: Difficulty,
+        mode: GenerationMode,
+        safe_area: SafeArea,
+        won: bool,
+        time: Duration,
+        bbbv: usize,
+    ) -> Record {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |date| date.as_secs());
+        Record {
+            difficulty,
+            mode,
+            safe_area,
+            won,
+            time,
+            bbbv,
+            date,
+        }
+    }
+
+    /// Appends the record to the file at `path`, creating it if it doesn't exist.
+    /// The records of an older version are written again in the current one
+    pub fn append(&self, path: &Path) -> Result<(), String> {
+        let err = |e: std::io::Error| format!("can't save the record of the game: {}", e);
+        let header = format!("{} {}", HEADER, RECORDS_VERSION);
+        match fs::read_to_string(path) {
+            Ok(s) if !s.is_empty() && s.lines().next() != Some(header.as_str()) => {
+                let mut records: Records = s.parse()?;
+                records.games.push(*self);
+                return fs::write(path, records.to_string()).map_err(err);
+            }
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(err(e)),
+            _ => (),
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(err)?;
+        if file.metadata().map_err(err)?.len() == 0 {
+            writeln!(file, "{} {}", HEADER, RECORDS_VERSION).map_err(err)?;
+        }
+        writeln!(file, "{}", self).map_err(err)
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Difficulty {
+            width,
+            height,
+            bombs,
+        } = self.difficulty;
+        let mode = match self.mode {
+            GenerationMode::Classic => "classic",
+            GenerationMode::NoGuess => "ng",
+        };
+        let safe_area = match self.safe_area {
+            SafeArea::Opening => "opening",
+            SafeArea::SafeTile => "st",
+            SafeArea::NoSafety => "ns",
+        };
+        let result = if self.won { "won" } else { "lost" };
+        write!(
+            f,
+            "{}x{}-{} {} {} {} {} {} {}",
+            width,
+            height,
+            bombs,
+            mode,
+            safe_area,
+            result,
+            self.time.as_millis(),
+            self.bbbv,
+            self.date
+        )
+    }
+}
+
+impl FromStr for Record {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Record, String> {
+        Record::parse(s, RECORDS_VERSION)
+    }
+}
+
+impl Record {
+    /// Reads a line of the records written in `version`
+    fn parse(s: &str, version: u32) -> Result<Record, String> {
+        let err = || format!("invalid record '{}'", s);
+        let mut fields: Vec<&str> = s.split_whitespace().collect();
+        if version == 1 && fields.len() == 6 {
+            fields.insert(2, "opening");
+        }
+        if fields.len() != 7 {
+            return Err(err());
+        }
+        let (size, bombs) = fields[0].split_once('-').ok_or_else(err)?;
+        let (width, height) = size.split_once('x').ok_or_else(err)?;
+        let number = |s: &str| s.parse::<u64>().map_err(|_| err());
+        let difficulty = Difficulty {
+            width: number(width)? as usize,
+            height: number(height)? as usize,
+            bombs: number(bombs)? as usize,
+        };
+        let mode = match fields[1] {
+            "classic" => GenerationMode::Classic,
+            "ng" => GenerationMode::NoGuess,
+            _ => return Err(err()),
+        };
+        let safe_area = match fields[2] {
+            "opening" => SafeArea::Opening,
+            "st" => SafeArea::SafeTile,
+            "ns" => SafeArea::NoSafety,
+            _ => return Err(err()),
+        };
+        let won = match fields[3] {
+            "won" => true,
+            "lost" => false,
+            _ => return Err(err()),
+        };
+        Ok(Record {
+            difficulty,
+            mode,
+            safe_area,
+            won,
+            time: Duration::from_millis(number(fields[4])?),
+            bbbv: number(fields[5])? as usize,
+            date: number(fields[6])?,
+        })
+    }
+}
+
+/// Every game recorded, from the oldest
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Records {
+    pub games: Vec<Record>,
+}
+
+impl Records {
+    /// Reads the records kept in the file at `path`, there are none if the file doesn't exist
+    pub fn load(path: &Path) -> Result<Records, String> {
+        match fs::read_to_string(path) {
+            Ok(s) => s.parse(),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Records::default()),
+            Err(e) => Err(format!("can't load the records: {}", e)),
+        }
+    }
+
+    /// Returns the statistics of the games played on fields of `difficulty`
+    /// with `mode` and `safe_area`
+    pub fn summary(
+        &self,
+        difficulty: Difficulty,
+        mode: GenerationMode,
+        safe_area: SafeArea,
+    ) -> Summary {
+        let mut summary = Summary::default();
+        let (mut total_time, mut total_bbbv) = (Duration::ZERO, 0);
+        let same_board = |game: &&Record| {
+            game.difficulty == difficulty && game.mode == mode && game.safe_area == safe_area
+        };
+        for game in self.games.iter().filter(same_board) {
+            summary.played += 1;
+            if game.won {
+                summary.won += 1;
+                summary.current_streak += 1;
+                summary.longest_streak = summary.longest_streak.max(summary.current_streak);
+                summary.best_time = Some(summary.best_time.map_or(game.time, |t| t.min(game.time)));
+                total_time += game.time;
+                total_bbbv += game.bbbv;
+            } else {
+                summary.current_streak = 0;
+            }
+        }
+        if summary.won > 0 {
+            summary.average_time = Some(total_time / summary.won as u32);
+            let secs = total_time.as_secs_f64();
+            if secs > 0.0 {
+                summary.average_bbbv_per_second = total_bbbv as f64 / secs;
+            }
+        }
+        summary
+    }
+}
+
+impl fmt::Display for Records {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", HEADER, RECORDS_VERSION)?;
+        for game in self.games.iter() {
+            writeln!(f, "{}", game)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Records {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Records, String> {
+        let mut lines = s.lines();
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(HEADER))
+            .ok_or_else(|| "the records are corrupted: the header is missing".to_string())?
+            .trim();
+        let version = match version.parse() {
+            Ok(version) if (1..=RECORDS_VERSION).contains(&version) => version,
+            _ => {
+                return Err(format!(
+                    "the records have version {}, only the versions up to {} are supported",
+                    version, RECORDS_VERSION
+                ))
+            }
+        };
+        let games = lines
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Record::parse(line, version))
+            .collect::<Result<_, _>>()?;
+        Ok(Records { games })
+    }
+}
+
+/// Statistics of the games played on the same kind of board
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Summary {
+    pub played: usize,
+    pub won: usize,
+    /// Wins in a row up to the last game
+    pub current_streak: usize,
+    pub longest_streak: usize,
+    /// Shortest time of the games won
+    pub best_time: Option<Duration>,
+    /// Average time of the games won
+    pub average_time: Option<Duration>,
+    /// Average 3BV solved every second in the games won
+    pub average_bbbv_per_second: f64,
+}
+
+impl Summary {
+    /// Returns the fraction of the games played that have been won
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 {
+            return 0.0;
+        }
+        self.won as f64 / self.played as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(difficulty: Difficulty, mode: GenerationMode, won: bool, secs: u64) -> Record {
+        Record {
+            difficulty,
+            mode,
+            safe_area: SafeArea::Opening,
+            won,
+            time: Duration::from_secs(secs),
+            bbbv: 30,
+            date: 1_760_000_000,
+        }
+    }
+
+    #[test]
+    fn record_display_and_parse_round_trip() {
+        let record = Record {
+            difficulty: Difficulty::EXPERT,
+            mode: GenerationMode::NoGuess,
+            safe_area: SafeArea::SafeTile,
+            won: false,
+            time: Duration::from_millis(5021),
+            bbbv: 143,
+            date: 1_760_000_100,
+        };
+        let line = "30x16-99 ng st lost 5021 143 1760000100";
+        assert_eq!(record.to_string(), line);
+        assert_eq!(line.parse(), Ok(record));
+    }
+
+    #[test]
+    fn records_display_and_parse_round_trip() {
+        let mut no_safety = game(Difficulty::EXPERT, GenerationMode::NoGuess, false, 5);
+        no_safety.safe_area = SafeArea::NoSafety;
+        let records = Records {
+            games: vec![
+                game(Difficulty::BEGINNER, GenerationMode::Classic, true, 12),
+                no_safety,
+            ],
+        };
+        assert_eq!(records.to_string().parse(), Ok(records));
+        assert_eq!("MineSweeper records 2\n".parse(), Ok(Records::default()));
+    }
+
+    #[test]
+    fn records_of_version_1_began_with_an_opening() {
+        let s = "MineSweeper records 1\n9x9-10 classic won 12345 17 1760000000\n";
+        let records: Records = s.parse().unwrap();
+        let mut record = game(Difficulty::BEGINNER, GenerationMode::Classic, true, 0);
+        record.time = Duration::from_millis(12345);
+        record.bbbv = 17;
+        assert_eq!(records.games, vec![record]);
+        // The lines of the current version must have the safe area
+        let line = "9x9-10 classic won 12345 17 1760000000";
+        assert!(line.parse::<Record>().is_err());
+        assert!(format!("MineSweeper records 2\n{}\n", line)
+            .parse::<Records>()
+            .is_err());
+    }
+
+    #[test]
+    fn malformed_records_are_refused() {
+        for line in [
+            "",
+            "9x9-10 classic opening won 12345 17",
+            "9x9-10 classic opening won 12345 17 1760000000 1",
+            "9x9 classic opening won 12345 17 1760000000",
+            "9-9x10 classic opening won 12345 17 1760000000",
+            "9x9-10 easy opening won 12345 17 1760000000",
+            "9x9-10 classic safe won 12345 17 1760000000",
+            "9x9-10 classic opening draw 12345 17 1760000000",
+            "9x9-10 classic opening won -1 17 1760000000",
+            "9x9-10 classic opening won 12345 many 1760000000",
+        ] {
+            assert_eq!(
+                line.parse::<Record>(),
+                Err(format!("invalid record '{}'", line))
+            );
+        }
+
+        let s = "MineSweeper records 2\n9x9-10 classic opening won 12345 17 1760000000\noops\n";
+        assert_eq!(
+            s.parse::<Records>(),
+            Err("invalid record 'oops'".to_string())
+        );
+        assert!("9x9-10 classic opening won 12345 17 1760000000\n"
+            .parse::<Records>()
+            .is_err());
+        for version in ["0", "3", "two"] {
+            assert_eq!(
+                format!("MineSweeper records {}\n", version).parse::<Records>(),
+                Err(format!(
+                    "the records have version {}, only the versions up to 2 are supported",
+                    version
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn summary_of_a_difficulty() {
+        let (beginner, classic) = (Difficulty::BEGINNER, GenerationMode::Classic);
+        let records = Records {
+            games: vec![
+                game(beginner, classic, true, 20),
+                game(beginner, classic, false, 3),
+                game(beginner, classic, true, 10),
+                // Games of other kinds of boards are left out
+                game(Difficulty::EXPERT, classic, true, 5),
+                game(beginner, GenerationMode::NoGuess, false, 5),
+                game(beginner, classic, true, 30),
+            ],
+        };
+        let summary = records.summary(beginner, classic, SafeArea::Opening);
+        assert_eq!(summary.played, 4);
+        assert_eq!(summary.won, 3);
+        assert_eq!(summary.win_rate(), 0.75);
+        assert_eq!(summary.best_time, Some(Duration::from_secs(10)));
+        assert_eq!(summary.average_time, Some(Duration::from_secs(20)));
+        assert_eq!(summary.average_bbbv_per_second, 1.5);
+
+        let empty = records.summary(Difficulty::INTERMEDIATE, classic, SafeArea::Opening);
+        assert_eq!(empty, Summary::default());
+        assert_eq!(empty.win_rate(), 0.0);
+    }
+
+    #[test]
+    fn safe_areas_are_kept_apart() {
+        let (beginner, classic) = (Difficulty::BEGINNER, GenerationMode::Classic);
+        let mut safe_tile = game(beginner, classic, true, 8);
+        safe_tile.safe_area = SafeArea::SafeTile;
+        let records = Records {
+            games: vec![game(beginner, classic, true, 20), safe_tile],
+        };
+        let opening = records.summary(beginner, classic, SafeArea::Opening);
+        assert_eq!(
+            (opening.played, opening.best_time),
+            (1, Some(Duration::from_secs(20)))
+        );
+        let safe_tile = records.summary(beginner, classic, SafeArea::SafeTile);
+        assert_eq!(
+            (safe_tile.played, safe_tile.best_time),
+            (1, Some(Duration::from_secs(8)))
+        );
+        assert_eq!(
+            records.summary(beginner, classic, SafeArea::NoSafety),
+            Summary::default()
+        );
+    }
+
+    #[test]
+    fn losses_reset_the_current_streak() {
+        let (beginner, classic) = (Difficulty::BEGINNER, GenerationMode::Classic);
+        let results = [true, true, true, false, true, false, true, true];
+        let expected = [
+            (1, 1),
+            (2, 2),
+            (3, 3),
+            (0, 3),
+            (1, 3),
+            (0, 3),
+            (1, 3),
+            (2, 3),
+        ];
+        let mut records = Records::default();
+        for (&won, &(current, longest)) in results.iter().zip(expected.iter()) {
+            records.games.push(game(beginner, classic, won, 10));
+            let summary = records.summary(beginner, classic, SafeArea::Opening);
+            assert_eq!(summary.current_streak, current);
+            assert_eq!(summary.longest_streak, longest);
+        }
+    }
+}
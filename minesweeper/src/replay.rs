@@ -0,0 +1,306 @@
+//! Recordings of the moves of a game.
+//!
+//! A replay is a text file made of a header with the version of the format,
+//! the `BoardId` of the game, the bombs of the field, one line for each row starting from `y = 0`
+//! (`*` for a bomb, `.` for an empty tile), and finally the actions of the player,
+//! each one with the milliseconds passed since the first tile got digged:
+//! ```text
+//! MineSweeper replay 1
+//! board 9x9-10-4,4-00000000000000ff
+//! mines
+//! ...*.....
+//! ...
+//! actions
+//! 0 dig 4 4
+//! 820 cursor 5 4
+//! 1540 flag 5 4
+//! ```
+//! The bombs are stored together with the seed because the no guess generation
+//! doesn't always give back the same field.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::board_id::BoardId;
+use crate::difficulty::{MAX_SIZE, MIN_SIZE};
+use crate::mine_field::{MineField, Outcome, TileState};
+
+/// Version of the replay format written by this crate
+pub const REPLAY_VERSION: u32 = 1;
+
+const HEADER: &str = "MineSweeper replay";
+
+/// Action of the player on the tile at `x`, `y`:
+/// * `Dig`: digs the tile
+///
+/// * `Flag`: puts or removes the flag on the tile
+///
+/// * `Chord`: digs around the number on the tile
+///
+/// * `Cursor`: moves the cursor over the tile
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Dig(usize, usize),
+    Flag(usize, usize),
+    Chord(usize, usize),
+    Cursor(usize, usize),
+}
+
+impl Action {
+    /// Does the action on `field`
+    pub fn apply(self, field: &mut MineField) -> Outcome {
+        match self {
+            Action::Dig(x, y) => field.dig(x, y),
+            Action::Flag(x, y) => field.flag(x, y),
+            Action::Chord(x, y) => field.chord(x, y),
+            Action::Cursor(..) => Outcome::Nothing,
+        }
+    }
+
+    /// Returns the coordinates of the tile of the action
+    pub fn tile(self) -> (usize, usize) {
+        match self {
+            Action::Dig(x, y) | Action::Flag(x, y) | Action::Chord(x, y) | Action::Cursor(x, y) => {
+                (x, y)
+            }
+        }
+    }
+}
+
+/// A game as a sequence of actions done on a field
+#[derive(Clone)]
+pub struct Replay {
+    /// The board that has been played
+    pub id: BoardId,
+    /// The field before the first action
+    pub field: MineField,
+    /// The actions together with the time passed since the first one
+    pub actions: Vec<(Duration, Action)>,
+}
+
+impl Replay {
+    /// Creates an empty `Replay` of the game played on `field`, which must not be touched yet
+    pub fn new(id: BoardId, field: &MineField) -> Replay {
+        Replay {
+            id,
+            field: field.clone(),
+            actions: Vec::new(),
+        }
+    }
+
+    /// Adds the `action` done at `time`
+    pub fn record(&mut self, time: Duration, action: Action) {
+        self.actions.push((time, action));
+    }
+
+    /// Returns how long the replay lasts
+    pub fn duration(&self) -> Duration {
+        self.actions
+            .last()
+            .map_or(Duration::from_secs(0), |&(time, _)| time)
+    }
+
+    /// Returns the field as it was at `time`, after all the actions done until then
+    pub fn field_at(&self, time: Duration) -> MineField {
+        let mut field = self.field.clone();
+        for &(_, action) in self.actions.iter().take_while(|&&(t, _)| t <= time) {
+            action.apply(&mut field);
+        }
+        field
+    }
+
+    /// Writes the replay to the file at `path`, replacing it if it exists
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_string()).map_err(|e| format!("can't save the replay: {}", e))
+    }
+
+    /// Reads the replay saved in the file at `path`
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        fs::read_to_string(path)
+            .map_err(|e| format!("can't load the replay: {}", e))?
+            .parse()
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", HEADER, REPLAY_VERSION)?;
+        writeln!(f, "board {}", self.id)?;
+        writeln!(f, "mines")?;
+        for y in 0..self.field.height() {
+            let row: String = (0..self.field.width())
+                .map(|x| if self.field.has_bomb(x, y) { '*' } else { '.' })
+                .collect();
+            writeln!(f, "{}", row)?;
+        }
+        writeln!(f, "actions")?;
+        for &(time, action) in self.actions.iter() {
+            let name = match action {
+                Action::Dig(..) => "dig",
+                Action::Flag(..) => "flag",
+                Action::Chord(..) => "chord",
+                Action::Cursor(..) => "cursor",
+            };
+            let (x, y) = action.tile();
+            writeln!(f, "{} {} {} {}", time.as_millis(), name, x, y)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Replay, String> {
+        let err = |what: &str| format!("the replay is corrupted: {}", what);
+        let mut lines = s.lines();
+        let mut entry = |name: &str| -> Result<&str, String> {
+            let line = lines.next().unwrap_or_default();
+            match line.strip_prefix(name) {
+                Some(value) => Ok(value.trim()),
+                None => Err(err(&format!("'{}' is missing", name.trim()))),
+            }
+        };
+        let version = entry(HEADER)?;
+        if version != REPLAY_VERSION.to_string() {
+            return Err(format!(
+                "the replay has version {}, only version {} is supported",
+                version, REPLAY_VERSION
+            ));
+        }
+        let id: BoardId = entry("board ")?.parse()?;
+        entry("mines")?;
+
+        // The size comes from the file, check it before making room for the tiles
+        let sizes = MIN_SIZE..=MAX_SIZE;
+        if !sizes.contains(&id.width) || !sizes.contains(&id.height) {
+            return Err(err(&format!("invalid size {}x{}", id.width, id.height)));
+        }
+        let mut tiles = vec![Vec::with_capacity(id.height); id.width];
+        for y in 0..id.height {
+            let row = lines.next().ok_or_else(|| err("too few rows"))?;
+            if row.chars().count() != id.width {
+                return Err(err(&format!("row {} has the wrong length", y)));
+            }
+            for (x, c) in row.chars().enumerate() {
+                let bomb = match c {
+                    '.' => false,
+                    '*' => true,
+                    _ => return Err(err(&format!("unknown tile '{}'", c))),
+                };
+                tiles[x].push((bomb, TileState::Normal));
+            }
+        }
+        let field = MineField::from_tiles(&tiles);
+        if field.bombs() != id.bombs {
+            return Err(err("wrong number of bombs"));
+        }
+        if lines.next() != Some("actions") {
+            return Err(err("'actions' is missing"));
+        }
+
+        let mut actions = Vec::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let invalid = || err(&format!("invalid action '{}'", line));
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() != 4 {
+                return Err(invalid());
+            }
+            let number = |i: usize| parts[i].parse::<usize>().map_err(|_| invalid());
+            let time = Duration::from_millis(parts[0].parse().map_err(|_| invalid())?);
+            let (x, y) = (number(2)?, number(3)?);
+            if x >= id.width || y >= id.height {
+                return Err(invalid());
+            }
+            let action = match parts[1] {
+                "dig" => Action::Dig(x, y),
+                "flag" => Action::Flag(x, y),
+                "chord" => Action::Chord(x, y),
+                "cursor" => Action::Cursor(x, y),
+                _ => return Err(invalid()),
+            };
+            actions.push((time, action));
+        }
+        Ok(Replay { id, field, actions })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mine_field::GamePhase;
+    use crate::solver;
+
+    /// A replay of a game won by digging and flagging what the solver finds
+    fn replay() -> Replay {
+        let id = BoardId::with_seed(7, 9, 9, 10, (4, 4));
+        let field = id.generate().unwrap();
+        let played = solver::play(&field, id.first_click);
+        let mut replay = Replay::new(id, &field);
+        replay.record(Duration::from_millis(0), Action::Dig(4, 4));
+        replay.record(Duration::from_millis(250), Action::Cursor(0, 8));
+        let mut time = 500;
+        for x in 0..9 {
+            for y in 0..9 {
+                let action = if played.is_flagged(x, y) {
+                    Action::Flag(x, y)
+                } else if played.is_digged(x, y) {
+                    Action::Dig(x, y)
+                } else {
+                    continue;
+                };
+                replay.record(Duration::from_millis(time), action);
+                time += 100;
+            }
+        }
+        replay.record(Duration::from_millis(time), Action::Chord(4, 4));
+        replay
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let replay = replay();
+        let text = replay.to_string();
+        let loaded: Replay = text.parse().unwrap();
+        assert_eq!(loaded.id, replay.id);
+        assert_eq!(loaded.actions, replay.actions);
+        assert_eq!(loaded.duration(), replay.duration());
+        assert_eq!(loaded.to_string(), text);
+
+        let end = loaded.field_at(loaded.duration());
+        assert_eq!(end.phase(), replay.field_at(replay.duration()).phase());
+        assert!(solver::is_cleared(&end));
+        assert_eq!(
+            loaded.field_at(Duration::from_millis(0)).phase(),
+            GamePhase::Playing
+        );
+    }
+
+    #[test]
+    fn sizes_are_checked_before_reading_the_tiles() {
+        let text = replay().to_string();
+        for size in ["99999999x99999999", "1x1", "9x100"].iter() {
+            let error = text.replace("9x9", size).parse::<Replay>().err();
+            assert!(error.unwrap().contains("width and height must be between"));
+        }
+    }
+
+    #[test]
+    fn corrupted_replays_are_refused() {
+        let text = replay().to_string();
+        let cases = [
+            text.replace("replay 1", "replay 2"),
+            text.replace("actions", "moves"),
+            text.replace("0 dig 4 4", "0 dig 4 9"),
+            text.replace("0 dig 4 4", "0 jump 4 4"),
+            text.replace("250 cursor", "later cursor"),
+            text.replacen("\n.", "\n*", 1),
+        ];
+        for case in cases.iter() {
+            assert!(case.parse::<Replay>().is_err(), "accepted:\n{}", case);
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//! RMV, the binary replay format shared by the Minesweeper clients.
+//!
+//! All the numbers are big endian. The file starts with the `*rmv` magic, the version of
+//! the format and the size of each of the following sections:
+//! * result string, version info, player info, board, preflags and properties,
+//!   extension (two bytes each)
+//!
+//! * events (four bytes)
+//!
+//! * checksum (two bytes)
+//!
+//! The checksum is the sum of the bytes of the sections and of the events that follow the header,
+//! wrapping at 16 bits. It's ignored when reading, the files written by old versions have none.
+//!
+//! The board is stored in the MBF format, see the `mbf` module.
+//! The events are mouse events made of a type (one byte), the milliseconds passed since
+//! the start of the game (three bytes) and the position of the mouse in pixels (two bytes each),
+//! with every tile being 16 pixels wide.
+//!
+//! Only the board and the mouse events are read back: the actions of the `Replay`
+//! are rebuilt by pressing the buttons on the field, the other sections are skipped.
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::board_id::BoardId;
+use crate::difficulty::Difficulty;
+use crate::mbf;
+use crate::mine_field::GamePhase;
+use crate::replay::{Action, Replay};
+
+/// Version of the RMV format written by this crate
+pub const RMV_VERSION: u16 = 1;
+
+const MAGIC: &[u8] = b"*rmv";
+const SQUARE_SIZE: usize = 16;
+
+const MOVE: u8 = 1;
+const LEFT_DOWN: u8 = 2;
+const LEFT_UP: u8 = 3;
+const RIGHT_DOWN: u8 = 4;
+const RIGHT_UP: u8 = 5;
+const MIDDLE_DOWN: u8 = 6;
+const MIDDLE_UP: u8 = 7;
+
+/// Reads the replay stored in `bytes`
+pub fn read(bytes: &[u8]) -> Result<Replay, String> {
+    let mut reader = Reader { bytes, offset: 0 };
+    if reader.take(4).ok() != Some(MAGIC) {
+        return Err("invalid RMV replay: this is not an RMV file".to_string());
+    }
+    let version = reader.u16()?;
+    if version != RMV_VERSION {
+        return Err(format!(
+            "the replay has RMV version {}, only version {} is supported",
+            version, RMV_VERSION
+        ));
+    }
+    let mut sizes = [0; 7];
+    for size in sizes.iter_mut() {
+        *size = reader.u16()? as usize;
+    }
+    let events_size = reader.u32()? as usize;
+    reader.u16()?;
+
+    let (before_board, after_board) = (sizes[..3].iter().sum(), sizes[4..].iter().sum());
+    reader.take(before_board)?;
+    let field = mbf::read(reader.take(sizes[3])?)?;
+    reader.take(after_board)?;
+    let mut events = Reader {
+        bytes: reader.take(events_size)?,
+        offset: 0,
+    };
+
+    let (width, height) = (field.width(), field.height());
+    let mut actions = Vec::new();
+    let mut played = field.clone();
+    let mut tile = None;
+    let (mut left, mut right, mut chorded) = (false, false, false);
+    while events.offset < events.bytes.len() {
+        let kind = events.u8()?;
+        if !(MOVE..=MIDDLE_UP).contains(&kind) {
+            // Board and game events of other clients, everything needed has been read
+            break;
+        }
+        let time = Duration::from_millis(events.u24()? as u64);
+        let (x, y) = (
+            events.u16()? as usize / SQUARE_SIZE,
+            events.u16()? as usize / SQUARE_SIZE,
+        );
+        let over = if x < width && y < height {
+            Some((x, height - 1 - y))
+        } else {
+            None
+        };
+        let digged = over.is_some_and(|(x, y)| played.is_digged(x, y));
+        let mut press = |action: fn(usize, usize) -> Action| {
+            if let Some((x, y)) = over {
+                let action = action(x, y);
+                action.apply(&mut played);
+                actions.push((time, action));
+            }
+        };
+        match kind {
+            MOVE if over != tile => {
+                tile = over;
+                press(Action::Cursor);
+            }
+            LEFT_DOWN => left = true,
+            LEFT_UP => {
+                if right {
+                    chorded = true;
+                    press(Action::Chord);
+                } else if chorded {
+                    chorded = false;
+                } else {
+                    press(if digged { Action::Chord } else { Action::Dig });
+                }
+                left = false;
+            }
+            RIGHT_DOWN => {
+                if !left {
+                    press(Action::Flag);
+                }
+                right = true;
+            }
+            RIGHT_UP => {
+                if left {
+                    chorded = true;
+                    press(Action::Chord);
+                } else if chorded {
+                    chorded = false;
+                }
+                right = false;
+            }
+            MIDDLE_UP => press(Action::Chord),
+            _ => (),
+        }
+    }
+
+    // The time of a `Replay` starts from the first tile that got digged
+    let first_dig = actions
+        .iter()
+        .find(|(_, action)| matches!(action, Action::Dig(..)))
+        .copied();
+    let first_click = first_dig.map_or((0, 0), |(_, action)| action.tile());
+    if let Some((start, _)) = first_dig {
+        actions.retain(|&(time, _)| time >= start);
+        for (time, _) in actions.iter_mut() {
+            *time -= start;
+        }
+    }
+    let id = BoardId::with_seed(0, width, height, field.bombs(), first_click);
+    Ok(Replay { id, field, actions })
+}
+
+/// Writes `replay` in the RMV format
+pub fn write(replay: &Replay) -> Result<Vec<u8>, String> {
+    let field = &replay.field;
+    let (width, height) = (field.width(), field.height());
+
+    let result = match replay.field_at(replay.duration()).phase() {
+        GamePhase::Won => "won",
+        GamePhase::Lost => "lost",
+        _ => "unfinished",
+    };
+    let version = concat!("MineSweeper ", env!("CARGO_PKG_VERSION"));
+
+    let board = mbf::write(field)?;
+
+    let difficulty = Difficulty {
+        width,
+        height,
+        bombs: field.bombs(),
+    };
+    let level = match difficulty {
+        Difficulty::BEGINNER => 0,
+        Difficulty::INTERMEDIATE => 1,
+        Difficulty::EXPERT => 2,
+        _ => 3,
+    };
+    // Question marks, no flags, classic mode and level
+    let properties = [0, 0, 0, level];
+
+    let mut events = Vec::new();
+    for &(time, action) in replay.actions.iter() {
+        let (x, y) = action.tile();
+        let (x, y) = (
+            (x * SQUARE_SIZE + SQUARE_SIZE / 2) as u16,
+            ((height - 1 - y) * SQUARE_SIZE + SQUARE_SIZE / 2) as u16,
+        );
+        let time = (time.as_millis() as u32).min(0xff_ffff);
+        let kinds: &[u8] = match action {
+            Action::Cursor(..) => &[MOVE],
+            Action::Dig(..) => &[MOVE, LEFT_DOWN, LEFT_UP],
+            Action::Flag(..) => &[MOVE, RIGHT_DOWN, RIGHT_UP],
+            Action::Chord(..) => &[MOVE, MIDDLE_DOWN, MIDDLE_UP],
+        };
+        for &kind in kinds {
+            events.push(kind);
+            events.extend_from_slice(&time.to_be_bytes()[1..]);
+            events.extend_from_slice(&x.to_be_bytes());
+            events.extend_from_slice(&y.to_be_bytes());
+        }
+    }
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&RMV_VERSION.to_be_bytes());
+    let sections: [&[u8]; 7] = [
+        result.as_bytes(),
+        version.as_bytes(),
+        &[],
+        &board,
+        &[],
+        &properties,
+        &[],
+    ];
+    for section in sections.iter() {
+        bytes.extend_from_slice(&(section.len() as u16).to_be_bytes());
+    }
+    bytes.extend_from_slice(&(events.len() as u32).to_be_bytes());
+    let mut body = sections.concat();
+    body.extend_from_slice(&events);
+    bytes.extend_from_slice(&checksum(&body).to_be_bytes());
+    bytes.extend_from_slice(&body);
+    Ok(bytes)
+}
+
+/// Returns the checksum of the bytes following the header
+fn checksum(body: &[u8]) -> u16 {
+    body.iter()
+        .fold(0, |sum: u16, &byte| sum.wrapping_add(byte as u16))
+}
+
+/// Reads the replay stored in the RMV file at `path`
+pub fn load(path: &Path) -> Result<Replay, String> {
+    read(&fs::read(path).map_err(|e| format!("can't load the replay: {}", e))?)
+}
+
+/// Writes `replay` to the RMV file at `path`, replacing it if it exists
+pub fn save(replay: &Replay, path: &Path) -> Result<(), String> {
+    fs::write(path, write(replay)?).map_err(|e| format!("can't save the replay: {}", e))
+}
+
+/// Reads big endian numbers one after the other
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset + len)
+            .ok_or_else(|| "invalid RMV replay: the file is truncated".to_string())?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u24(&mut self) -> Result<u32, String> {
+        let bytes = self.take(3)?;
+        Ok(u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mine_field::MineField;
+
+    /// Offset of the checksum: magic, version, section sizes and events size
+    const CHECKSUM: usize = 4 + 2 + 7 * 2 + 4;
+
+    fn bombs(field: &MineField) -> Vec<(usize, usize)> {
+        let mut bombs = Vec::new();
+        for x in 0..field.width() {
+            for y in 0..field.height() {
+                if field.has_bomb(x, y) {
+                    bombs.push((x, y));
+                }
+            }
+        }
+        bombs
+    }
+
+    #[test]
+    fn write_and_read_round_trip() {
+        let id = BoardId::with_seed(5, 9, 9, 10, (2, 6));
+        let field = id.generate().unwrap();
+        let mut replay = Replay::new(id, &field);
+        // The mouse moves over a tile before clicking it, so the cursor is read back
+        // whenever it gets on another tile
+        let moves = [
+            (0, Action::Cursor(2, 6)),
+            (0, Action::Dig(2, 6)),
+            (300, Action::Cursor(5, 5)),
+            (700, Action::Cursor(8, 0)),
+            (900, Action::Flag(8, 0)),
+            (1200, Action::Flag(8, 0)),
+            (1500, Action::Cursor(2, 6)),
+            (1800, Action::Chord(2, 6)),
+        ];
+        for &(time, action) in moves.iter() {
+            replay.record(Duration::from_millis(time), action);
+        }
+
+        let bytes = write(&replay).unwrap();
+        assert_eq!(&bytes[..4], MAGIC);
+        let body = &bytes[CHECKSUM + 2..];
+        assert_eq!(
+            &bytes[CHECKSUM..CHECKSUM + 2],
+            &checksum(body).to_be_bytes()
+        );
+        let read = read(&bytes).unwrap();
+        assert_eq!(bombs(&read.field), bombs(&field));
+        assert_eq!(read.actions, replay.actions);
+        assert_eq!(read.id.first_click, id.first_click);
+        assert_eq!((read.id.width, read.id.height, read.id.bombs), (9, 9, 10));
+    }
+
+    #[test]
+    fn reads_a_reference_file() {
+        #[rustfmt::skip]
+        let bytes = [
+            b'*', b'r', b'm', b'v', 0, 1,
+            // Result, version, player, board, preflags, properties and extension sizes
+            0, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0, 4, 0, 0,
+            // Events size and checksum
+            0, 0, 0, 38, 0x05, 0xf6,
+            b'w', b'o', b'n',
+            b'x',
+            // 2x2 board with a bomb in the top left corner
+            2, 2, 0, 1, 0, 0,
+            0, 0, 0, 3,
+            // Left click on the bottom right tile after a second
+            2, 0x00, 0x03, 0xe8, 0, 24, 0, 24,
+            3, 0x00, 0x03, 0xe8, 0, 24, 0, 24,
+            // Right click on the top left tile after two seconds
+            4, 0x00, 0x07, 0xd0, 0, 8, 0, 8,
+            5, 0x00, 0x07, 0xd0, 0, 8, 0, 8,
+            // Event of another client, which ends the mouse events
+            16, 0, 0, 0, 0, 0,
+        ];
+        assert_eq!(checksum(&bytes[CHECKSUM + 2..]), 0x05f6);
+        let replay = read(&bytes).unwrap();
+        assert_eq!(bombs(&replay.field), vec![(0, 1)]);
+        assert_eq!(
+            replay.actions,
+            vec![
+                (Duration::from_millis(0), Action::Dig(1, 0)),
+                (Duration::from_millis(1000), Action::Flag(0, 1)),
+            ]
+        );
+        assert_eq!(replay.id.first_click, (1, 0));
+        assert!(replay.field_at(replay.duration()).is_flagged(0, 1));
+
+        assert!(read(&bytes[..30]).is_err());
+        let mut other = bytes;
+        other[5] = 2;
+        assert!(read(&other).err().unwrap().contains("version 2"));
+        other[0] = b'#';
+        assert!(read(&other).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Save files of the games left in progress.
+//!
+//! A save is a text file made of a header with the version of the format,
+//! the `BoardId` of the game, the time spent playing in milliseconds and finally the tiles,
+//! one line for each row starting from `y = 0`:
+//! ```text
+//! MineSweeper save 1
+//! board 9x9-10-4,4-00000000000000ff
+//! elapsed 12345
+//! tiles
+//! ooof*....
+//! ...
+//! ```
+//! Each tile is written as one of these characters:
+//! * `.` hidden tile, `*` hidden bomb
+//! * `f` flag on a tile without a bomb, `F` flag on a bomb
+//! * `o` digged tile, `x` digged bomb
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::board_id::BoardId;
+use crate::difficulty::{MAX_SIZE, MIN_SIZE};
+use crate::mine_field::{MineField, TileState};
+
+/// Version of the save format written by this crate
+pub const SAVE_VERSION: u32 = 1;
+
+const HEADER: &str = "MineSweeper save";
+
+/// A game with everything needed to resume it
+#[derive(Clone)]
+pub struct SavedGame {
+    /// The board that is being played
+    pub id: BoardId,
+    /// The field as the player left it
+    pub field: MineField,
+    /// Time spent playing
+    pub elapsed: Duration,
+}
+
+impl SavedGame {
+    /// Writes the game to the file at `path`, replacing it if it exists
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_string()).map_err(|e| format!("can't save the game: {}", e))
+    }
+
+    /// Reads the game saved in the file at `path`
+    pub fn load(path: &Path) -> Result<SavedGame, String> {
+        fs::read_to_string(path)
+            .map_err(|e| format!("can't load the saved game: {}", e))?
+            .parse()
+    }
+}
+
+impl fmt::Display for SavedGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", HEADER, SAVE_VERSION)?;
+        writeln!(f, "board {}", self.id)?;
+        writeln!(f, "elapsed {}", self.elapsed.as_millis())?;
+        writeln!(f, "tiles")?;
+        for y in 0..self.field.height() {
+            let row: String = (0..self.field.width())
+                .map(|x| {
+                    let bomb = self.field.has_bomb(x, y);
+                    match (self.field.state(x, y), bomb) {
+                        (TileState::Normal, false) => '.',
+                        (TileState::Normal, true) => '*',
+                        (TileState::Flagged, false) => 'f',
+                        (TileState::Flagged, true) => 'F',
+                        (TileState::Digged, false) => 'o',
+                        (TileState::Digged, true) => 'x',
+                    }
+                })
+                .collect();
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SavedGame {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<SavedGame, String> {
+        let err = |what: &str| format!("the saved game is corrupted: {}", what);
+        let mut lines = s.lines();
+        let mut entry = |name: &str| -> Result<&str, String> {
+            let line = lines.next().unwrap_or_default();
+            match line.strip_prefix(name) {
+                Some(value) => Ok(value.trim()),
+                None => Err(err(&format!("'{}' is missing", name.trim()))),
+            }
+        };
+        let version = entry(HEADER)?;
+        if version != SAVE_VERSION.to_string() {
+            return Err(format!(
+                "the saved game has version {}, only version {} is supported",
+                version, SAVE_VERSION
+            ));
+        }
+        let id: BoardId = entry("board ")?.parse()?;
+        let elapsed = entry("elapsed ")?
+            .parse()
+            .map(Duration::from_millis)
+            .map_err(|_| err("invalid elapsed time"))?;
+        entry("tiles")?;
+
+        // The size comes from the file, check it before making room for the tiles
+        let sizes = MIN_SIZE..=MAX_SIZE;
+        if !sizes.contains(&id.width) || !sizes.contains(&id.height) {
+            return Err(err(&format!("invalid size {}x{}", id.width, id.height)));
+        }
+        let mut tiles = vec![Vec::with_capacity(id.height); id.width];
+        for y in 0..id.height {
+            let row = lines.next().ok_or_else(|| err("too few rows"))?;
+            if row.chars().count() != id.width {
+                return Err(err(&format!("row {} has the wrong length", y)));
+            }
+            for (x, c) in row.chars().enumerate() {
+                let tile = match c {
+                    '.' => (false, TileState::Normal),
+                    '*' => (true, TileState::Normal),
+                    'f' => (false, TileState::Flagged),
+                    'F' => (true, TileState::Flagged),
+                    'o' => (false, TileState::Digged),
+                    'x' => (true, TileState::Digged),
+                    _ => return Err(err(&format!("unknown tile '{}'", c))),
+                };
+                tiles[x].push(tile);
+            }
+        }
+        if lines.any(|line| !line.trim().is_empty()) {
+            return Err(err("too many rows"));
+        }
+        let field = MineField::from_tiles(&tiles);
+        if field.bombs() != id.bombs {
+            return Err(err("wrong number of bombs"));
+        }
+        Ok(SavedGame { id, field, elapsed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved_game() -> SavedGame {
+        let id = BoardId::with_seed(42, 9, 7, 10, (4, 3));
+        let mut field = id.generate().unwrap();
+        field.dig(4, 3);
+        let bomb = (0..9)
+            .flat_map(|x| (0..7).map(move |y| (x, y)))
+            .find(|&(x, y)| field.has_bomb(x, y))
+            .unwrap();
+        field.flag(bomb.0, bomb.1);
+        field.flag(0, 0);
+        SavedGame {
+            id,
+            field,
+            elapsed: Duration::from_millis(12_345),
+        }
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let game = saved_game();
+        let text = game.to_string();
+        let loaded: SavedGame = text.parse().unwrap();
+        assert_eq!(loaded.id, game.id);
+        assert_eq!(loaded.elapsed, game.elapsed);
+        assert_eq!(loaded.field.phase(), game.field.phase());
+        for x in 0..9 {
+            for y in 0..7 {
+                assert!(loaded.field.state(x, y) == game.field.state(x, y));
+                assert_eq!(loaded.field.has_bomb(x, y), game.field.has_bomb(x, y));
+            }
+        }
+        assert_eq!(loaded.to_string(), text);
+    }
+
+    #[test]
+    fn sizes_are_checked_before_reading_the_tiles() {
+        let text = saved_game().to_string();
+        for size in ["99999999x99999999", "1x1", "9x100"].iter() {
+            let error = text.replace("9x7", size).parse::<SavedGame>().err();
+            assert!(error.unwrap().contains("width and height must be between"));
+        }
+    }
+
+    #[test]
+    fn corrupted_saves_are_refused() {
+        let text = saved_game().to_string();
+        let (header, rows) = text.split_at(text.find("tiles\n").unwrap() + 6);
+        let cases = [
+            text.replace("save 1", "save 2"),
+            text.replace("elapsed 12345", "elapsed soon"),
+            format!("{}?{}", header, &rows[1..]),
+            text.lines().take(8).collect::<Vec<_>>().join("\n"),
+            format!("{}......*..\n", text),
+        ];
+        for case in cases.iter() {
+            assert!(case.parse::<SavedGame>().is_err(), "accepted:\n{}", case);
+        }
+    }
+}
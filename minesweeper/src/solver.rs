@@ -0,0 +1,344 @@
+//! Deterministic logic solver.
+//!
+//! The solver only looks at what the player can see: the numbers of the digged tiles,
+//! the flags and the hidden tiles, plus the total number of bombs of the field.
+//! Flags are trusted to be on top of bombs.
+
+use crate::mine_field::MineField;
+
+/// The rules the solver uses, from the simplest to the most advanced:
+/// * `Single`: a number that already has all its bombs or that needs
+///   all its hidden neighbours to be bombs
+///
+/// * `Pair`: two numbers that share some hidden neighbours,
+///   including the case where the neighbours of one are a subset of the other's
+///
+/// * `Global`: the total number of bombs left on the field
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rule {
+    Single,
+    Pair,
+    Global,
+}
+
+/// Hidden tiles whose content has been proven
+#[derive(Clone, Debug, Default)]
+pub struct Deductions {
+    /// Tiles that surely don't have a bomb
+    pub safe: Vec<(usize, usize)>,
+    /// Tiles that surely have a bomb
+    pub bombs: Vec<(usize, usize)>,
+    /// The most advanced rule that was needed to find them
+    pub hardest: Option<Rule>,
+}
+
+impl Deductions {
+    /// Tells whether or not nothing could be proven
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.bombs.is_empty()
+    }
+}
+
+/// What is known of a hidden tile
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Knowledge {
+    Unknown,
+    Safe,
+    Bomb,
+}
+
+/// Exactly `bombs` of the unknown `tiles` contain a bomb
+pub(crate) struct Constraint {
+    pub(crate) tiles: Vec<(usize, usize)>,
+    pub(crate) bombs: usize,
+}
+
+/// Returns every hidden tile of `field` that can be proven to be safe or to have a bomb.
+///
+/// The rules are applied over and over, using what was proven by one to feed the others,
+/// until nothing new can be found.
+pub fn solve(field: &MineField) -> Deductions {
+    let (w, h) = (field.width(), field.height());
+    let mut known = vec![vec![Knowledge::Unknown; h]; w];
+    let mut deductions = Deductions::default();
+
+    loop {
+        let constraints = constraints(field, &known);
+        let mut found = Vec::new();
+        let mut rule = Rule::Single;
+
+        for c in constraints.iter() {
+            single(c, &mut found);
+        }
+        if found.is_empty() {
+            rule = Rule::Pair;
+            pairs(field, &constraints, &mut found);
+        }
+        if found.is_empty() {
+            rule = Rule::Global;
+            if let Some(global) = global(field, &known, &constraints) {
+                single(&global, &mut found);
+                for c in constraints.iter() {
+                    pair(c, &global, &mut found);
+                }
+            }
+        }
+        if found.is_empty() {
+            break;
+        }
+        for (x, y, bomb) in found {
+            if known[x][y] != Knowledge::Unknown {
+                continue;
+            }
+            if bomb {
+                known[x][y] = Knowledge::Bomb;
+                deductions.bombs.push((x, y));
+            } else {
+                known[x][y] = Knowledge::Safe;
+                deductions.safe.push((x, y));
+            }
+        }
+        deductions.hardest = deductions.hardest.max(Some(rule));
+    }
+    deductions
+}
+
+/// Plays the game on a copy of `field` starting by digging `start`,
+/// flagging and digging only what can be proven, until it gets stuck.
+/// The copy is returned so that the caller can see how far it went.
+pub fn play(field: &MineField, start: (usize, usize)) -> MineField {
+    let mut field = field.clone();
+    if field.has_bomb(start.0, start.1) {
+        return field;
+    }
+    field.dig(start.0, start.1);
+
+    loop {
+        let Deductions { safe, bombs, .. } = solve(&field);
+        if safe.is_empty() && bombs.is_empty() {
+            break;
+        }
+        for (x, y) in bombs {
+            field.flag(x, y);
+        }
+        for (x, y) in safe {
+            field.dig(x, y);
+        }
+    }
+    field
+}
+
+/// Tells whether or not every tile without a bomb has been digged
+pub fn is_cleared(field: &MineField) -> bool {
+    (0..field.width())
+        .all(|x| (0..field.height()).all(|y| field.has_bomb(x, y) || field.is_digged(x, y)))
+}
+
+/// Builds a constraint for every digged number that has unknown hidden neighbours
+pub(crate) fn constraints(field: &MineField, known: &[Vec<Knowledge>]) -> Vec<Constraint> {
+    let mut constraints = Vec::new();
+    for x in 0..field.width() {
+        for y in 0..field.height() {
+            if !field.is_digged(x, y) {
+                continue;
+            }
+            let mut tiles = Vec::new();
+            let mut bombs = 0;
+            for (x, y) in field.neighbours(x, y) {
+                if field.is_flagged(x, y) || known[x][y] == Knowledge::Bomb {
+                    bombs += 1;
+                } else if field.is_normal(x, y) && known[x][y] == Knowledge::Unknown {
+                    tiles.push((x, y));
+                }
+            }
+            if tiles.is_empty() {
+                continue;
+            }
+            constraints.push(Constraint {
+                tiles,
+                bombs: (field.bombs_near(x, y) as usize).saturating_sub(bombs),
+            });
+        }
+    }
+    constraints
+}
+
+/// Single tile rule: all the tiles are safe or all of them have a bomb
+fn single(c: &Constraint, found: &mut Vec<(usize, usize, bool)>) {
+    if c.bombs == 0 {
+        found.extend(c.tiles.iter().map(|&(x, y)| (x, y, false)));
+    } else if c.bombs == c.tiles.len() {
+        found.extend(c.tiles.iter().map(|&(x, y)| (x, y, true)));
+    }
+}
+
+/// Applies the pair rule to every couple of constraints that share at least one tile
+fn pairs(field: &MineField, constraints: &[Constraint], found: &mut Vec<(usize, usize, bool)>) {
+    // For each tile the list of the constraints that contain it
+    let mut owners = vec![vec![Vec::new(); field.height()]; field.width()];
+    for (i, c) in constraints.iter().enumerate() {
+        for &(x, y) in c.tiles.iter() {
+            owners[x][y].push(i);
+        }
+    }
+    for (i, a) in constraints.iter().enumerate() {
+        let mut near: Vec<usize> = a
+            .tiles
+            .iter()
+            .flat_map(|&(x, y)| owners[x][y].iter().cloned())
+            .filter(|&j| j != i)
+            .collect();
+        near.sort_unstable();
+        near.dedup();
+
+        for j in near {
+            pair(a, &constraints[j], found);
+        }
+    }
+}
+
+/// Pair rule: looks at how many of the bombs of `b` can be in the tiles shared with `a`
+/// to find out if the other tiles of `b` are all safe or all have a bomb.
+///
+/// When the tiles of `a` are a subset of the ones of `b` this is the classic subset rule.
+fn pair(a: &Constraint, b: &Constraint, found: &mut Vec<(usize, usize, bool)>) {
+    let only_b: Vec<(usize, usize)> = b
+        .tiles
+        .iter()
+        .filter(|t| !a.tiles.contains(t))
+        .cloned()
+        .collect();
+    if only_b.is_empty() {
+        return;
+    }
+    let shared = b.tiles.len() - only_b.len();
+    let only_a = a.tiles.len() - shared;
+
+    // Range of bombs that can be in the shared tiles
+    let low = a
+        .bombs
+        .saturating_sub(only_a)
+        .max(b.bombs.saturating_sub(only_b.len()));
+    let high = a.bombs.min(b.bombs).min(shared);
+    if low > high {
+        return;
+    }
+    if b.bombs == low {
+        found.extend(only_b.iter().map(|&(x, y)| (x, y, false)));
+    } else if b.bombs - high == only_b.len() {
+        found.extend(only_b.iter().map(|&(x, y)| (x, y, true)));
+    }
+}
+
+/// Global rule: builds a constraint on the unknown tiles using the number of bombs left.
+///
+/// The bombs inside a group of numbers that don't share any tile are exactly known,
+/// so they can be removed from the constraint together with their tiles.
+fn global(
+    field: &MineField,
+    known: &[Vec<Knowledge>],
+    constraints: &[Constraint],
+) -> Option<Constraint> {
+    let mut left = field.bombs();
+    let mut unknown = Vec::new();
+    for (x, column) in known.iter().enumerate() {
+        for (y, &knowledge) in column.iter().enumerate() {
+            if field.is_flagged(x, y) || knowledge == Knowledge::Bomb {
+                left = left.checked_sub(1)?;
+            } else if field.is_normal(x, y) && knowledge == Knowledge::Unknown {
+                unknown.push((x, y));
+            }
+        }
+    }
+    let mut covered = vec![vec![false; field.height()]; field.width()];
+    for c in constraints.iter() {
+        if c.tiles.iter().all(|&(x, y)| !covered[x][y]) {
+            for &(x, y) in c.tiles.iter() {
+                covered[x][y] = true;
+            }
+            left = left.checked_sub(c.bombs)?;
+        }
+    }
+    let tiles: Vec<(usize, usize)> = unknown
+        .into_iter()
+        .filter(|&(x, y)| !covered[x][y])
+        .collect();
+    if tiles.is_empty() || left > tiles.len() {
+        None
+    } else {
+        Some(Constraint { tiles, bombs: left })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut tiles: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        tiles.sort_unstable();
+        tiles
+    }
+
+    #[test]
+    fn single_rule_finds_bombs_and_safe_tiles() {
+        // The digged tile in the corner touches a single hidden tile and has a 1
+        let field = MineField::from_rows(&["*o", "oo"]);
+        let deductions = solve(&field);
+        assert_eq!(deductions.bombs, vec![(0, 0)]);
+        assert!(deductions.safe.is_empty());
+        assert_eq!(deductions.hardest, Some(Rule::Single));
+
+        // The 1 next to the flag already has its bomb
+        let field = MineField::from_rows(&["Fo.", "ooo"]);
+        let deductions = solve(&field);
+        assert_eq!(deductions.safe, vec![(2, 0)]);
+        assert!(deductions.bombs.is_empty());
+        assert_eq!(deductions.hardest, Some(Rule::Single));
+    }
+
+    #[test]
+    fn pair_rule_solves_a_one_two_one() {
+        let field = MineField::from_rows(&["*.*", "ooo"]);
+        let deductions = solve(&field);
+        assert_eq!(sorted(deductions.bombs), vec![(0, 0), (2, 0)]);
+        assert_eq!(deductions.safe, vec![(1, 0)]);
+        assert_eq!(deductions.hardest, Some(Rule::Pair));
+    }
+
+    #[test]
+    fn global_rule_counts_the_bombs_left() {
+        // The only bomb is next to the digged corner, so all the other tiles are safe
+        let field = MineField::from_rows(&["o*..", "....", "....", "...."]);
+        let deductions = solve(&field);
+        assert!(deductions.bombs.is_empty());
+        assert_eq!(deductions.safe.len(), 12);
+        assert!(deductions.safe.iter().all(|&(x, y)| x > 1 || y > 1));
+        assert_eq!(deductions.hardest, Some(Rule::Global));
+
+        // With one more bomb somewhere else nothing can be said
+        let field = MineField::from_rows(&["o*..", "....", "....", "...*"]);
+        assert!(solve(&field).is_empty());
+    }
+
+    #[test]
+    fn play_clears_a_board_without_guesses() {
+        let field = MineField::from_rows(&["....", "....", "....", "...*"]);
+        assert!(!is_cleared(&field));
+        let played = play(&field, (0, 0));
+        assert!(is_cleared(&played));
+        assert!(played.is_flagged(3, 3));
+        // The field given is left as it is
+        assert!(field.is_normal(0, 0));
+    }
+
+    #[test]
+    fn play_stops_at_a_guess() {
+        // Once the right side is open the bomb can be in either tile of the left column
+        let field = MineField::from_rows(&["*..", "..."]);
+        let played = play(&field, (2, 0));
+        assert!(!is_cleared(&played));
+        assert!(played.is_digged(1, 0) && played.is_digged(1, 1));
+        assert!(played.is_normal(0, 0) && played.is_normal(0, 1));
+        assert_eq!(played.bombs_near(1, 1), 1);
+    }
+}
@@ -0,0 +1,303 @@
+//! Statistics of the boards and of the games played on them.
+//!
+//! The 3BV (Bechtel's Board Benchmark Value) of a board is the fewest left clicks needed
+//! to clear it without chording: one for each opening, the area of tiles without bombs near
+//! that gets digged at once together with its border, and one for each number outside of them.
+//! The islands are the groups of touching numbers outside of the openings.
+//! ZiNi estimates the fewest clicks needed when chording is allowed, flags included,
+//! by chording the number that saves the most clicks until no chord saves any.
+
+use std::time::Duration;
+
+use crate::mine_field::MineField;
+
+/// Statistics of the mine layout of a board
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoardStats {
+    /// Bechtel's Board Benchmark Value
+    pub bbbv: usize,
+    pub openings: usize,
+    pub islands: usize,
+    pub zini: usize,
+}
+
+/// Statistics of the play of a game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameStats {
+    /// 3BV of the tiles that got digged
+    pub solved_bbbv: usize,
+    /// Digs, flags and chords done, even the useless ones
+    pub clicks: usize,
+    pub time: Duration,
+}
+
+impl GameStats {
+    /// Returns the efficiency (IOE), the 3BV solved for every click
+    pub fn efficiency(&self) -> f64 {
+        if self.clicks == 0 {
+            return 0.0;
+        }
+        self.solved_bbbv as f64 / self.clicks as f64
+    }
+
+    /// Returns the 3BV solved every second
+    pub fn bbbv_per_second(&self) -> f64 {
+        let secs = self.time.as_secs_f64();
+        if secs == 0.0 {
+            return 0.0;
+        }
+        self.solved_bbbv as f64 / secs
+    }
+}
+
+/// The clicks a board can be cleared with, without chording
+struct Units {
+    /// Opening of each tile without bombs near, indexed as `opening[x][y]`
+    opening: Vec<Vec<Option<usize>>>,
+    /// Whether each tile is a number outside of the openings, indexed as `isolated[x][y]`
+    isolated: Vec<Vec<bool>>,
+    openings: usize,
+}
+
+impl Units {
+    /// Returns how many of the units have still to be digged in `field`
+    fn left(&self, field: &MineField) -> usize {
+        let mut opened = vec![false; self.openings];
+        let mut left = 0;
+        for x in 0..field.width() {
+            for y in 0..field.height() {
+                if let Some(i) = self.opening[x][y] {
+                    opened[i] |= field.is_digged(x, y);
+                } else if self.isolated[x][y] && !field.is_digged(x, y) {
+                    left += 1;
+                }
+            }
+        }
+        left + opened.iter().filter(|&&opened| !opened).count()
+    }
+}
+
+impl MineField {
+    /// Returns the statistics of the bombs of the field, wherever they have been digged or flagged
+    pub fn stats(&self) -> BoardStats {
+        let units = self.units();
+        let (w, h) = (self.width(), self.height());
+
+        let mut islands = 0;
+        let mut seen = vec![vec![false; h]; w];
+        for x in 0..w {
+            for y in 0..h {
+                if !units.isolated[x][y] || seen[x][y] {
+                    continue;
+                }
+                islands += 1;
+                seen[x][y] = true;
+                let mut island = vec![(x, y)];
+                while let Some((x, y)) = island.pop() {
+                    for (x, y) in self.neighbours(x, y) {
+                        if units.isolated[x][y] && !seen[x][y] {
+                            seen[x][y] = true;
+                            island.push((x, y));
+                        }
+                    }
+                }
+            }
+        }
+
+        let field = self.layout();
+        BoardStats {
+            bbbv: units.left(&field),
+            openings: units.openings,
+            islands,
+            zini: self.zini(&units),
+        }
+    }
+
+    /// Returns the 3BV of the tiles digged so far
+    pub fn solved_bbbv(&self) -> usize {
+        let units = self.units();
+        units.left(&self.layout()) - units.left(self)
+    }
+
+    /// Returns a copy of the field with the same bombs and every tile hidden
+    fn layout(&self) -> MineField {
+        let mut field = MineField::new(self.width(), self.height());
+        for x in 0..self.width() {
+            for y in 0..self.height() {
+                if self.has_bomb(x, y) {
+                    field.add_bomb_at(x, y);
+                }
+            }
+        }
+        field
+    }
+
+    /// Finds the openings by digging every tile without bombs near,
+    /// the numbers left hidden are the ones outside of the openings
+    fn units(&self) -> Units {
+        let (w, h) = (self.width(), self.height());
+        let mut field = self.layout();
+        let mut opening = vec![vec![None; h]; w];
+        let mut openings = 0;
+        for x in 0..w {
+            for y in 0..h {
+                if self.has_bomb(x, y) || self.bombs_near(x, y) != 0 || field.is_digged(x, y) {
+                    continue;
+                }
+                for &(x, y, digged) in field.dig(x, y).changes() {
+                    if digged && self.bombs_near(x, y) == 0 {
+                        opening[x][y] = Some(openings);
+                    }
+                }
+                openings += 1;
+            }
+        }
+        let isolated = (0..w)
+            .map(|x| {
+                (0..h)
+                    .map(|y| !self.has_bomb(x, y) && !field.is_digged(x, y))
+                    .collect()
+            })
+            .collect();
+        Units {
+            opening,
+            isolated,
+            openings,
+        }
+    }
+
+    /// Clears a copy of the field doing every time the chord that saves the most clicks,
+    /// then digs what is left one unit at a time
+    fn zini(&self, units: &Units) -> usize {
+        let mut field = self.layout();
+        let mut clicks = 0;
+        while !field.is_over() {
+            let mut best = None;
+            let mut best_premium = 0;
+            for x in 0..field.width() {
+                for y in 0..field.height() {
+                    if field.has_bomb(x, y) || field.bombs_near(x, y) == 0 {
+                        continue;
+                    }
+                    let cost = field.chord_cost(x, y);
+                    let saved = field.chord_units(x, y, units);
+                    if saved as isize - cost as isize > best_premium {
+                        best_premium = saved as isize - cost as isize;
+                        best = Some((x, y));
+                    }
+                }
+            }
+            let (x, y) = match best {
+                Some(tile) => tile,
+                None => break,
+            };
+            clicks += field.chord_cost(x, y);
+            field.dig(x, y);
+            for (x, y) in field.neighbours(x, y) {
+                if field.has_bomb(x, y) && !field.is_flagged(x, y) {
+                    field.flag(x, y);
+                }
+            }
+            field.chord(x, y);
+        }
+        clicks + units.left(&field)
+    }
+
+    /// Returns the clicks needed to chord the number at `x`, `y`:
+    /// one to dig it if it's hidden, one for each flag missing around it and the chord itself
+    fn chord_cost(&self, x: usize, y: usize) -> usize {
+        let flags = self
+            .neighbours(x, y)
+            .into_iter()
+            .filter(|&(x, y)| self.has_bomb(x, y) && !self.is_flagged(x, y))
+            .count();
+        usize::from(!self.is_digged(x, y)) + flags + 1
+    }
+
+    /// Returns how many of the units still hidden would be digged by chording the number at `x`, `y`
+    fn chord_units(&self, x: usize, y: usize, units: &Units) -> usize {
+        let mut tiles = self.neighbours(x, y);
+        tiles.push((x, y));
+        let mut openings: Vec<usize> = Vec::new();
+        let mut isolated = 0;
+        for (x, y) in tiles {
+            if self.has_bomb(x, y) || self.is_digged(x, y) {
+                continue;
+            }
+            match units.opening[x][y] {
+                Some(i) if !openings.contains(&i) => openings.push(i),
+                Some(_) => (),
+                None if units.isolated[x][y] => isolated += 1,
+                None => (),
+            }
+        }
+        openings.len() + isolated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn board_with_a_single_opening() {
+        // Every number touches the opening, so a click is enough
+        let field = MineField::from_rows(&["*....", ".....", ".....", ".....", "....."]);
+        let stats = BoardStats {
+            bbbv: 1,
+            openings: 1,
+            islands: 0,
+            zini: 1,
+        };
+        assert_eq!(field.stats(), stats);
+    }
+
+    #[test]
+    fn board_with_numbers_only() {
+        // The 8 numbers around the bomb make a single island: the chords around
+        // the first two digged numbers leave only a corner to dig, 3 + 1 + 1 clicks
+        let field = MineField::from_rows(&["...", ".*.", "..."]);
+        let stats = BoardStats {
+            bbbv: 8,
+            openings: 0,
+            islands: 1,
+            zini: 5,
+        };
+        assert_eq!(field.stats(), stats);
+    }
+
+    #[test]
+    fn board_with_two_islands() {
+        // The 2s are cut off from each other and from the opening on the right by the bombs
+        let mut field = MineField::from_rows(&["*.*.*...."]);
+        let stats = field.stats();
+        assert_eq!((stats.bbbv, stats.openings, stats.islands), (3, 1, 2));
+
+        assert_eq!(field.solved_bbbv(), 0);
+        field.dig(1, 0);
+        assert_eq!(field.solved_bbbv(), 1);
+        field.dig(8, 0);
+        assert_eq!(field.solved_bbbv(), 2);
+        // The stats of the board don't change while playing
+        assert_eq!(field.stats(), stats);
+    }
+
+    #[test]
+    fn game_stats() {
+        let game = GameStats {
+            solved_bbbv: 30,
+            clicks: 40,
+            time: Duration::from_secs(12),
+        };
+        assert_eq!(game.efficiency(), 0.75);
+        assert_eq!(game.bbbv_per_second(), 2.5);
+
+        let idle = GameStats {
+            solved_bbbv: 0,
+            clicks: 0,
+            time: Duration::from_secs(0),
+        };
+        assert_eq!(idle.efficiency(), 0.0);
+        assert_eq!(idle.bbbv_per_second(), 0.0);
+    }
+}
@@ -7,10 +7,15 @@ flat in int vs_TextureIndex;
 uniform sampler2D texture0;
 uniform sampler2D texture1;
 uniform sampler2D texture2;
+uniform sampler2D texture3;
 
 uniform int texture0_idx;
 uniform int texture1_idx;
 uniform int texture2_idx;
+uniform int texture3_idx;
+
+uniform vec4 tint = vec4(1);
+uniform vec4 color_filter = vec4(1);
 
 out vec4 fragColor;
 
@@ -27,8 +32,13 @@ void main(void)
 
 	else if (vs_TextureIndex == texture2_idx)
 		color = texture(texture2, vs_TextureCoord);
+
+	else if (vs_TextureIndex == texture3_idx)
+		color = texture(texture3, vs_TextureCoord);
 	else
 		color = vec4(1, 0, 1, 1);
+
+	color *= tint * color_filter;
 	
 	if (color.a > 0.1)
 		fragColor = color;
@@ -0,0 +1,74 @@
+use std::borrow::Cow;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+// Environment variable with the folder whose files replace the embedded ones
+pub const ASSETS_VAR: &str = "MINESWEEPER_ASSETS";
+
+// Pairs the name of a file of the assets folder with its content
+macro_rules! embed {
+    ($name:literal) => {
+        ($name, include_bytes!(concat!("../", $name)) as &[u8])
+    };
+}
+
+// The shaders and the textures of the game, built into the executable so that it runs from any folder
+const EMBEDDED: &[(&str, &[u8])] = &[
+    embed!("shaders/vertex.glsl"),
+    embed!("shaders/fragment.glsl"),
+    embed!("textures/Sprite-Sand(big).png"),
+    embed!("textures/Sprite-Props.png"),
+    embed!("textures/Sprite-UI-new.png"),
+    embed!("textures/Font.png"),
+    embed!("textures/atlas.toml"),
+];
+
+// Where the files of the game are taken from: the override folder when it has them,
+// the executable otherwise
+pub struct Assets {
+    dir: Option<PathBuf>,
+}
+
+impl Assets {
+    // The override folder is the one in the environment variable, or else `dir` from the settings
+    pub fn new(dir: Option<PathBuf>) -> Result<Assets, String> {
+        let dir = env::var_os(ASSETS_VAR).map(PathBuf::from).or(dir);
+        if let Some(dir) = dir.as_ref().filter(|dir| !dir.is_dir()) {
+            return Err(format!("the assets folder {} doesn't exist", dir.display()));
+        }
+        Ok(Assets { dir })
+    }
+
+    // Returns the content of the file `name`, a path relative to the assets folder
+    pub fn load(&self, name: &str) -> Result<Cow<'static, [u8]>, String> {
+        if let Some(path) = self.dir.as_ref().map(|dir| dir.join(name)) {
+            if path.exists() {
+                return fs::read(&path)
+                    .map(Cow::Owned)
+                    .map_err(|e| format!("can't read the asset {}: {}", path.display(), e));
+            }
+        }
+        EMBEDDED
+            .iter()
+            .find(|&&(embedded, _)| embedded == name)
+            .map(|&(_, bytes)| Cow::Borrowed(bytes))
+            .ok_or_else(|| match &self.dir {
+                Some(dir) => format!(
+                    "the asset {} is missing: it isn't in {} nor built into the game",
+                    name,
+                    dir.display()
+                ),
+                None => format!(
+                    "the asset {} is missing: it isn't built into the game",
+                    name
+                ),
+            })
+    }
+
+    // Returns the content of the text file `name`
+    pub fn load_string(&self, name: &str) -> Result<String, String> {
+        String::from_utf8(self.load(name)?.into_owned())
+            .map_err(|_| format!("the asset {} isn't valid UTF-8 text", name))
+    }
+}
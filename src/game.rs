@@ -0,0 +1,1932 @@
+use std::f32::consts::FRAC_PI_2;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use minesweeper::{
+    mbf, rmv, Action, BoardId, Budget, Difficulty, GamePhase, GameStats, GenerationMode, MineField,
+    Outcome, Record, Replay, SavedGame,
+};
+use rand::distributions::{Distribution, Uniform};
+use rand::rngs::ThreadRng;
+use sdl2::controller::Axis;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::mouse::MouseButton;
+
+use crate::graphics::mesh::Mesh;
+use crate::graphics::text::{self, Align, Font};
+use crate::graphics::vbo::VertexBufferObject as VBO;
+use crate::graphics::vertex::Vertex;
+use crate::input::{Command, Input, StickRepeat, Touch, TOUCH_MOUSE_ID};
+use crate::menu::MenuScene;
+use crate::pause::{PauseChoice, PauseScene};
+use crate::scene::{self, Animation, Context, Scene, Transition};
+use crate::theme::Theme;
+use crate::{
+    advance_frame, apply_sprite, apply_tile, data_dir, mix, move_rect, move_texture_rect,
+    put_number, put_rect, put_unit_square, reset_rect, rotate_tecture_rect, INK, PX, RECORDS_FILE,
+    SAVE_FILE, WHITE,
+};
+
+const REPLAY_DIR: &str = "replays"; // Where the replays of the games are written, in the data folder
+const SEEK_STEP: Duration = Duration::from_secs(5); // How much the arrows move a replay
+const PAD_PAN_SPEED: f32 = 2.0; // Window heights the right stick pans every second
+const PAD_ZOOM_SPEED: f32 = 1.5; // How fast the triggers zoom
+const PINCH_ZOOM: f32 = 4.0; // How much pinching zooms, relative to the size of the screen
+const HUD_SIZE: usize = 13 * 6; // Vertices of the hud, which doesn't move with the field
+const TEXT_SIZE: usize = 32 * 6; // Room for the text, which is written only when it's shown
+const RESULT_TIME: Duration = Duration::from_secs(4); // How long the result of a game is shown
+
+// Where the view goes after a bomb has been digged: first the bomb, then the centre
+// of the field once all the bombs are shown
+#[derive(Clone, Copy)]
+struct Bomb {
+    tile: (usize, usize),
+    // Seconds since the view started moving, and where it started from
+    time: f32,
+    offset: (f32, f32),
+    scale: f32,
+}
+
+// The mine field being played or replayed
+pub struct GameScene {
+    mesh: Mesh,
+    difficulty: Difficulty,
+    mode: GenerationMode,
+    field: MineField,
+    // Board imported with '--board', played instead of placing the bombs at random
+    imported: Option<MineField>,
+    from_file: bool,
+    w: usize,
+    h: usize,
+    max_scale: f32,
+    // Variations of the textures
+    tile_distr: Uniform<u8>,
+    // Theme the field has been textured with
+    theme: String,
+    ground_data: Vec<Vertex>,
+    props_data: Vec<Vertex>,
+    ui_data: Vec<Vertex>,
+    hud_data: Vec<Vertex>,
+    // Offsets within the buffer of the various parts, the ground is first
+    props_offset: usize,
+    ui_offset: usize,
+    hud_offset: usize,
+    text_offset: usize,
+    // Flags for the animation
+    // Format is: ll corner, l side, ul corner, u side, ur corner, r side, lr corner, l side
+    border_flags: Vec<bool>,
+    flags: Vec<(usize, usize)>,
+    selected: (usize, usize),       // Selected tile
+    cursor: Option<(usize, usize)>, // Tile pointed by the cursor
+    // Scale and offset of the mine field
+    scale: f32,
+    offset: (f32, f32),
+    // Board being played, known once the bombs have been placed
+    board: Option<BoardId>,
+    // Time spent playing before the current session and when it began
+    played: Duration,
+    playing_since: Option<Instant>,
+    // Whether or not the clock has been stopped by the pause menu, to start it again after it
+    clock_paused: bool,
+    // The replay of a new game, the resumed ones aren't recorded as their beginning is missing
+    recording: Option<Replay>,
+    // Position, speed and state of the replay being played back
+    replay_time: Duration,
+    replay_speed: f32,
+    replay_paused: bool,
+    next_action: usize,
+    replaying: bool,
+    // Digs, flags and chords done and how long the game lasted, for the statistics
+    clicks: usize,
+    game_time: Option<Duration>,
+    // Time since the last tick of the animated tiles
+    tick: Duration,
+    second_tick: bool,
+    // Button that digs or pans being held, with where and when it got pressed
+    dig_button: Option<(MouseButton, i32, i32, Instant)>,
+    flag_button: bool,
+    // Whether or not both buttons have been pressed together to chord
+    chording: bool,
+    // Actions to do on the field once the events have been handled
+    actions: Vec<Action>,
+    dragging: bool,
+    init: bool,
+    bomb: Option<Bomb>,
+    bomb_stage: u8,
+    update_ground: bool,
+    update_props: bool,
+    update_ui: bool,
+    block_click: bool,
+    hovering_next: bool,
+    // The field zooming in when the game begins, the events wait for it
+    zoom: Option<Animation>,
+    left_stick: StickRepeat,
+    // Where the keys or the sticks move the selected tile this frame
+    step: Option<(i32, i32)>,
+    // The finger that can tap or long press, how many are on the screen
+    // and where the last pinch was centred
+    touch: Option<Touch>,
+    fingers: usize,
+    pinch: Option<(f32, f32)>,
+}
+
+impl GameScene {
+    // Sets up a field of the size of `difficulty`, showing the `resumed` game if there is one
+    pub fn new(
+        ctx: &mut Context,
+        difficulty: Difficulty,
+        mode: GenerationMode,
+        resumed: Option<SavedGame>,
+    ) -> Result<GameScene, String> {
+        let imported = ctx.imported.take();
+        let from_file = imported.is_some();
+        let mut field = MineField::new(difficulty.width, difficulty.height);
+        let w = field.width();
+        let h = field.height();
+        let max_scale = fit_scale(w, h, ctx.aspect);
+
+        // Create a uniform distribution that goes from 0 to 4(excluded)
+        // (used for generating variations on the texture)
+        let tile_distr = Uniform::from(0..4);
+
+        // Allocate the memory for storing the ground, props and ui data
+        let ground_size = (w + 2) * (h + 2) * 6;
+        let mut ground_data: Vec<Vertex> = vec![Default::default(); ground_size];
+        let props_size = w * h * 6;
+        let mut props_data: Vec<Vertex> = vec![Default::default(); props_size];
+        let ui_size = 12;
+        let mut ui_data: Vec<Vertex> = vec![Default::default(); ui_size];
+
+        let selected = ((w + 2) / 2, (h + 2) / 2);
+        let theme = &ctx.theme;
+        let rng = &mut ctx.rng;
+
+        // Set up the mine field ground textures and vertices
+        setup_ground(&mut ground_data, w, h, theme, rng, &tile_distr);
+
+        // Set up the mine field props textures and vertices
+        setup_props(&mut props_data, w, h, theme, rng, &tile_distr);
+        let mut board = None;
+        let mut played = Duration::from_secs(0);
+        let mut playing_since = None;
+        let mut flags = Vec::new();
+
+        // Show the tiles of the saved game as they were left
+        if let Some(game) = resumed {
+            field = game.field;
+            board = Some(game.id);
+            played = game.elapsed;
+            playing_since = Some(Instant::now());
+            flags = flagged_tiles(&field);
+            show_field(
+                &mut ground_data,
+                &mut props_data,
+                &field,
+                theme,
+                rng,
+                &tile_distr,
+            );
+        }
+        if let Some(replay) = &ctx.playback {
+            field = replay.field.clone();
+            board = Some(replay.id);
+        }
+
+        // Put the cursor
+        put_unit_square(&mut ui_data, 0, selected.0 as f32, selected.1 as f32);
+        apply_sprite(&mut ui_data, 0, &theme.ui, theme.atlas.ui.selected);
+        // Put the cursor
+        put_unit_square(&mut ui_data, 6, selected.0 as f32, selected.1 as f32);
+        apply_sprite(&mut ui_data, 6, &theme.ui, theme.atlas.ui.cursor);
+
+        // Offsets within the buffer of the various parts
+        let props_offset = ground_size;
+        let ui_offset = props_offset + props_size;
+        let hud_offset = ui_offset + ui_size;
+        let text_offset = hud_offset + HUD_SIZE;
+
+        let mesh = Mesh::new(&ctx.program, text_offset + TEXT_SIZE)?;
+        VBO::write(0, &ground_data);
+        VBO::write(props_offset, &props_data);
+        VBO::write(ui_offset, &ui_data);
+
+        let animations = ctx.settings.game.animations;
+        Ok(GameScene {
+            mesh,
+            difficulty,
+            mode,
+            field,
+            imported,
+            from_file,
+            w,
+            h,
+            max_scale,
+            tile_distr,
+            theme: ctx.settings.game.theme.clone(),
+            ground_data,
+            props_data,
+            ui_data,
+            hud_data: vec![Default::default(); HUD_SIZE],
+            props_offset,
+            ui_offset,
+            hud_offset,
+            text_offset,
+            border_flags: vec![false; (w + 2) * 2 + h * 2],
+            flags,
+            selected,
+            cursor: None,
+            scale: 0.001,
+            offset: ((w + 2) as f32 / -2.0, (h + 2) as f32 / -2.0),
+            init: board.is_none(),
+            board,
+            played,
+            playing_since,
+            clock_paused: false,
+            recording: None,
+            replay_time: Duration::from_secs(0),
+            replay_speed: 1.0,
+            replay_paused: false,
+            next_action: 0,
+            replaying: ctx.playback.is_some(),
+            clicks: 0,
+            game_time: None,
+            tick: Duration::from_secs(0),
+            second_tick: false,
+            dig_button: None,
+            flag_button: false,
+            chording: false,
+            actions: Vec::new(),
+            dragging: false,
+            bomb: None,
+            bomb_stage: 0,
+            update_ground: false,
+            update_props: false,
+            update_ui: false,
+            block_click: false,
+            hovering_next: false,
+            zoom: Some(Animation::new(animations, Duration::from_secs(1))),
+            left_stick: StickRepeat::default(),
+            step: None,
+            touch: None,
+            fingers: 0,
+            pinch: None,
+        })
+    }
+
+    // Returns the time spent playing so far, across the sessions
+    fn playing_time(&self) -> Duration {
+        self.played
+            + self
+                .playing_since
+                .map_or(Duration::from_secs(0), |t| t.elapsed())
+    }
+
+    // Keeps the game in progress to continue it the next time
+    fn save_game(&self) -> Result<(), String> {
+        if let (Some(id), false, false) = (self.board, self.field.is_over(), self.replaying) {
+            let elapsed = self.playing_time();
+            let field = self.field.clone();
+            fs::create_dir_all(data_dir()).map_err(|e| e.to_string())?;
+            SavedGame { id, field, elapsed }.save(&data_dir().join(SAVE_FILE))?;
+        }
+        Ok(())
+    }
+
+    // Ends the game, keeping the replay and the record, then shows whether it's been `won`
+    // unless a new one has to start right away or the game has been left to go back to the menu
+    fn finish(
+        &mut self,
+        ctx: &mut Context,
+        won: bool,
+        back_to_menu: bool,
+    ) -> Result<Transition, String> {
+        // The game is over, so the saved one can't be continued anymore
+        if !self.replaying && !back_to_menu {
+            let _ = fs::remove_file(data_dir().join(SAVE_FILE));
+        }
+        // Only the games played until the end are kept, not the ones left halfway
+        let mut saved = None;
+        if let (Some(replay), true) = (&self.recording, self.field.is_over()) {
+            saved = Some(save_replay(replay)?);
+        }
+        // The imported boards are left out of the records, their bombs haven't been placed
+        // at random
+        if let (Some(time), Some(id), false, false) =
+            (self.game_time, self.board, self.replaying, self.from_file)
+        {
+            let won = self.field.phase() == GamePhase::Won;
+            let bbbv = self.field.stats().bbbv;
+            let record = Record::now(self.difficulty, id.mode, id.safe_area, won, time, bbbv);
+            fs::create_dir_all(data_dir()).map_err(|e| e.to_string())?;
+            record.append(&data_dir().join(RECORDS_FILE))?;
+        }
+        if ctx.restart.is_some() || back_to_menu {
+            return Ok(Transition::Replace(Box::new(MenuScene::new(ctx)?)));
+        }
+        let field = [self.ground_data.as_slice(), &self.props_data].concat();
+        // The board id is shown so that the board can be shared or played again
+        let mut lines: Vec<String> = self.board.iter().map(|id| id.to_string()).collect();
+        // The resumed games miss the clicks done before quitting
+        if let (Some(time), true) = (self.game_time, self.recording.is_some() || self.replaying) {
+            lines.extend(stats_lines(&self.field, self.clicks, time));
+        }
+        lines.extend(saved.map(|name| format!("replay saved as {}", name)));
+        let text = lines.join("\n");
+        let result = ResultScene::new(
+            ctx,
+            field,
+            self.offset,
+            self.scale,
+            won,
+            self.replaying,
+            text,
+        )?;
+        Ok(Transition::Replace(Box::new(result)))
+    }
+
+    // Plays again on a new field of the same size
+    fn restart(&mut self, ctx: &mut Context) -> Result<Transition, String> {
+        ctx.restart = Some((self.difficulty, self.mode));
+        self.finish(ctx, false, false)
+    }
+
+    // Makes the next game be played on the same board as this one
+    fn replay_board(&mut self, ctx: &mut Context) {
+        if self.from_file {
+            ctx.imported = match &self.recording {
+                Some(replay) => Some(replay.field.clone()),
+                None => self.imported.take(),
+            };
+        } else {
+            ctx.board = self.board;
+        }
+    }
+
+    // Stops the clock and opens the pause menu on top of the field
+    fn pause(&mut self, ctx: &mut Context) -> Result<Transition, String> {
+        if let Some(since) = self.playing_since.take() {
+            self.played += since.elapsed();
+            self.clock_paused = true;
+        }
+        // The buttons and the fingers held get released on the pause menu
+        self.dig_button = None;
+        self.flag_button = false;
+        self.chording = false;
+        self.dragging = false;
+        self.touch = None;
+        self.fingers = 0;
+        self.pinch = None;
+        // The field can't be shown again with another theme once it's over
+        let themes = !self.field.is_over();
+        Ok(Transition::Push(Box::new(PauseScene::new(ctx, themes)?)))
+    }
+
+    // Textures the field again with the theme chosen in the options, which must not be over,
+    // the borders start their animation over
+    fn retexture(&mut self, ctx: &mut Context) {
+        let (w, h) = (self.w, self.h);
+        let theme = &ctx.theme;
+        let rng = &mut ctx.rng;
+        setup_ground(&mut self.ground_data, w, h, theme, rng, &self.tile_distr);
+        setup_props(&mut self.props_data, w, h, theme, rng, &self.tile_distr);
+        show_field(
+            &mut self.ground_data,
+            &mut self.props_data,
+            &self.field,
+            theme,
+            rng,
+            &self.tile_distr,
+        );
+        apply_sprite(&mut self.ui_data, 0, &theme.ui, theme.atlas.ui.selected);
+        apply_sprite(&mut self.ui_data, 6, &theme.ui, theme.atlas.ui.cursor);
+        self.border_flags.iter_mut().for_each(|flag| *flag = false);
+        self.theme = ctx.settings.game.theme.clone();
+
+        self.mesh.bind();
+        VBO::write(0, &self.ground_data);
+        VBO::write(self.props_offset, &self.props_data);
+        VBO::write(self.ui_offset, &self.ui_data);
+    }
+
+    // Takes the field back to before the bomb got digged, when a replay is sought back
+    // from the explosion: the grass covers the craters and the bombs shown
+    fn undo_explosion(&mut self, ctx: &mut Context) {
+        let theme = &ctx.theme;
+        self.block_click = false;
+        self.hovering_next = false;
+        self.bomb = None;
+        self.bomb_stage = 0;
+        self.scale = self.max_scale;
+        self.clamp_offset();
+        let (w, h) = (self.w, self.h);
+        setup_props(
+            &mut self.props_data,
+            w,
+            h,
+            theme,
+            &mut ctx.rng,
+            &self.tile_distr,
+        );
+        // The page that came down over the field becomes the cursor again
+        let (sx, sy) = (self.selected.0 as f32, self.selected.1 as f32);
+        put_unit_square(&mut self.ui_data, 6, sx, sy);
+        apply_sprite(&mut self.ui_data, 6, &theme.ui, theme.atlas.ui.cursor);
+        self.update_ui = true;
+    }
+
+    // Keeps the offset inside the field
+    fn clamp_offset(&mut self) {
+        let (w, h) = (self.w as f32, self.h as f32);
+        self.offset.0 = self.offset.0.max(-w - 1.5).min(-0.5);
+        self.offset.1 = self.offset.1.max(-h - 1.5).min(-0.5);
+    }
+
+    // Does the actions, they can't change anything once a bomb has been digged.
+    // Returns the transition once the game has been won
+    fn apply_actions(&mut self, ctx: &mut Context) -> Result<Option<Transition>, String> {
+        let (w, h) = (self.w, self.h);
+        let (ground, props) = (ctx.theme.ground, ctx.theme.props);
+        let (ground_atlas, props_atlas) = (&ctx.theme.atlas.ground, &ctx.theme.atlas.props);
+        let actions: Vec<Action> = self.actions.drain(..).collect();
+        for action in actions {
+            if self.block_click {
+                break;
+            }
+            if let (true, Action::Dig(x, y)) = (self.init, action) {
+                self.init = false;
+                // Place the bombs, unless they have been imported
+                let bombs = self.difficulty.bombs;
+                let mut id = match (ctx.board.take(), ctx.seed.take()) {
+                    (Some(id), _) => id,
+                    (None, Some(seed)) => BoardId::with_seed(seed, w, h, bombs, (x, y)),
+                    (None, None) => BoardId::new(w, h, bombs, (x, y)),
+                };
+                id.mode = self.mode;
+                self.field = match self.imported.take() {
+                    Some(imported) => imported,
+                    // A no guess board that takes too long is replaced by a classic one
+                    None => id
+                        .generate_within(Budget::default())
+                        .map_err(|e| e.to_string())?,
+                };
+                self.board = Some(id);
+                self.playing_since = Some(Instant::now());
+                self.recording = Some(Replay::new(id, &self.field));
+            }
+            let time = self.playing_time();
+            if let Some(replay) = &mut self.recording {
+                replay.record(time, action);
+            }
+            if let Action::Dig(..) | Action::Flag(..) | Action::Chord(..) = action {
+                self.clicks += 1;
+            }
+            let (x, y) = action.tile();
+            let outcome = action.apply(&mut self.field);
+            if self.field.is_over() && self.game_time.is_none() {
+                self.game_time = Some(if self.replaying {
+                    self.replay_time
+                } else {
+                    self.playing_time()
+                });
+            }
+            match action {
+                Action::Cursor(..) => {
+                    put_unit_square(&mut self.ui_data, 6, (x + 1) as f32, (y + 1) as f32);
+                    self.update_ui = true;
+                    ctx.redraw = true;
+                }
+                Action::Flag(..) if outcome != Outcome::Nothing => {
+                    self.selected = (x + 1, y + 1);
+                    // Move the cursor
+                    let (sx, sy) = (self.selected.0 as f32, self.selected.1 as f32);
+                    put_unit_square(&mut self.ui_data, 0, sx, sy);
+
+                    let index = (y * w + x) * 6;
+                    if self.field.is_flagged(x, y) {
+                        self.flags.push((x, y));
+
+                        move_texture_rect(
+                            &mut self.props_data,
+                            index,
+                            Some(props.column(props_atlas.flag)),
+                            Some(props.row(0)),
+                        );
+                    } else {
+                        self.flags.retain(|&v| v != (x, y));
+
+                        move_texture_rect(
+                            &mut self.props_data,
+                            index,
+                            Some(props.column(props_atlas.grass)),
+                            Some(props.row(0)),
+                        );
+                    }
+                    VBO::write(
+                        self.props_offset + index,
+                        &self.props_data[index..(index + 6)],
+                    );
+                    ctx.redraw = true;
+                }
+                Action::Flag(..) => {}
+                Action::Dig(..) | Action::Chord(..) => {
+                    self.selected = (x + 1, y + 1);
+                    // Move the cursor
+                    let (sx, sy) = (self.selected.0 as f32, self.selected.1 as f32);
+                    put_unit_square(&mut self.ui_data, 0, sx, sy);
+                    self.update_ui = true;
+                    ctx.redraw = true;
+                }
+            }
+            for &(x, y, i) in outcome.changes() {
+                move_texture_rect(
+                    &mut self.ground_data,
+                    ((y + 1) * (w + 2) + x + 1) * 6,
+                    Some(ground.column(ground_atlas.border(self.field.border_type(x, y)))),
+                    None,
+                );
+                if self.field.has_bomb(x, y) {
+                    let variant = self.tile_distr.sample(&mut ctx.rng) as u32;
+                    move_texture_rect(
+                        &mut self.props_data,
+                        (y * w + x) * 6,
+                        Some(props.column(props_atlas.bomb + variant)),
+                        Some(props.row(0)),
+                    );
+                } else if i {
+                    let near = self.field.bombs_near(x, y);
+                    move_texture_rect(
+                        &mut self.props_data,
+                        (y * w + x) * 6,
+                        Some(props.column(props_atlas.number(near))),
+                        Some(props.row(self.tile_distr.sample(&mut ctx.rng) as u32)),
+                    );
+                }
+            }
+            if !outcome.changes().is_empty() {
+                self.update_ground = true;
+                self.update_props = true;
+                ctx.redraw = true;
+            }
+            match outcome {
+                Outcome::Exploded(x, y, _) => {
+                    self.block_click = true;
+                    self.cursor = None;
+                    self.bomb = Some(Bomb {
+                        tile: (x, y),
+                        time: 0.0,
+                        offset: self.offset,
+                        scale: self.scale,
+                    });
+                }
+                Outcome::Won(_) => return self.finish(ctx, true, false).map(Some),
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    // Moves the view to the digged bomb, then back to the whole field once the bombs are shown
+    fn follow_bomb(&mut self, ctx: &mut Context, dt: Duration) -> Result<(), String> {
+        let b = match &mut self.bomb {
+            Some(b) => {
+                b.time += dt.as_secs_f32();
+                *b
+            }
+            None => return Ok(()),
+        };
+        let elapsed = b.time;
+        if elapsed >= 1.5 {
+            return Ok(());
+        }
+        ctx.redraw = true;
+
+        if elapsed < 1.0 {
+            self.offset.0 = mix(b.offset.0, -(b.tile.0 as f32 + 1.5), elapsed);
+            self.offset.1 = mix(b.offset.1, -(b.tile.1 as f32 + 1.5), elapsed);
+        } else {
+            self.offset.0 = -(b.tile.0 as f32 + 1.5);
+            self.offset.1 = -(b.tile.1 as f32 + 1.5);
+        }
+        match self.bomb_stage {
+            n @ 0..=2 => {
+                if elapsed < 1.0 {
+                    self.scale = mix(b.scale, 1.0, elapsed);
+                } else if n == 2 {
+                    self.scale = 1.0;
+                }
+            }
+            3 => {
+                let aspect = ctx.aspect;
+                if elapsed < 1.0 {
+                    self.scale = mix(1.0, self.max_scale, elapsed);
+                    let y = aspect.1 - elapsed * 0.25;
+                    move_rect(&mut self.ui_data, 6, None, Some((y, 0.25)));
+                } else {
+                    move_rect(&mut self.ui_data, 6, None, Some((aspect.1 - 0.25, 0.25)));
+                    self.scale = self.max_scale;
+                    self.bomb = None;
+                }
+                self.update_ui = true;
+            }
+            _ => return Err("Impossible state!".to_string()),
+        }
+        Ok(())
+    }
+
+    // Plays the animations of the tiles, the flags, the borders and the cursor
+    fn play_tick(&mut self, ctx: &mut Context) {
+        let (w, h) = (self.w, self.h);
+        let theme = &ctx.theme;
+        let (ground, props) = (theme.ground, theme.props);
+        let (ground_atlas, props_atlas) = (&theme.atlas.ground, &theme.atlas.props);
+        self.second_tick = !self.second_tick;
+
+        if let Some(b) = self.bomb {
+            let index = (b.tile.1 * w + b.tile.0) * 6;
+            match self.bomb_stage {
+                0 => {
+                    if self.second_tick && advance_frame(&mut self.props_data, index, props.tile.1)
+                    {
+                        self.bomb_stage = 1;
+                        move_texture_rect(
+                            &mut self.props_data,
+                            index,
+                            Some(props.column(props_atlas.explosion.0)),
+                            None,
+                        );
+                    }
+                }
+                1 => {
+                    if advance_frame(&mut self.props_data, index, props.tile.1) {
+                        self.bomb_stage = 2;
+                        move_texture_rect(
+                            &mut self.props_data,
+                            index,
+                            Some(props.column(props_atlas.explosion.1)),
+                            None,
+                        );
+                    }
+                }
+                2 => {
+                    if advance_frame(&mut self.props_data, index, props.tile.1) {
+                        put_rect(&mut self.ui_data, 6, 0.0, ctx.aspect.1, 2.0, 0.25);
+                        let edge = theme.atlas.ui.page_edge;
+                        apply_sprite(&mut self.ui_data, 6, &theme.ui, edge);
+                        self.bomb_stage = 3;
+                        move_texture_rect(
+                            &mut self.props_data,
+                            index,
+                            None,
+                            Some(props.row(props_atlas.crater)),
+                        );
+                        self.bomb = Some(Bomb {
+                            tile: (w / 2, h / 2),
+                            time: 0.0,
+                            offset: self.offset,
+                            scale: 1.0,
+                        });
+                        self.flags.clear();
+                        self.field.update_all();
+                        for x in 0..w {
+                            for y in 0..h {
+                                if self.field.has_bomb(x, y) {
+                                    let column = if self.field.is_flagged(x, y) {
+                                        props_atlas.flagged_bomb
+                                    } else {
+                                        props_atlas.hidden_bomb
+                                    };
+                                    let row = self.tile_distr.sample(&mut ctx.rng) as u32;
+                                    move_texture_rect(
+                                        &mut self.props_data,
+                                        (y * w + x) * 6,
+                                        Some(props.column(column)),
+                                        Some(props.row(row)),
+                                    );
+                                } else {
+                                    move_texture_rect(
+                                        &mut self.props_data,
+                                        (y * w + x) * 6,
+                                        Some(props.column(props_atlas.number(0))),
+                                        None,
+                                    );
+                                }
+                                let border = ground_atlas.border(self.field.border_type(x, y));
+                                move_texture_rect(
+                                    &mut self.ground_data,
+                                    ((y + 1) * (w + 2) + x + 1) * 6,
+                                    Some(ground.column(border)),
+                                    None,
+                                );
+                            }
+                        }
+                    }
+                }
+                3 => {}
+                _ => unreachable!(),
+            }
+        }
+
+        for (x, y) in self.flags.iter() {
+            let index = (y * w + x) * 6;
+            advance_frame(&mut self.props_data, index, props.tile.1);
+        }
+
+        // Update the borders
+        update_borders(
+            &mut self.ground_data,
+            &mut self.border_flags,
+            w,
+            h,
+            ground.tile.1,
+        );
+        // Play the cursor animation
+        advance_frame(&mut self.ui_data, 0, theme.ui.tile.1);
+
+        self.update_props = true;
+        self.update_ground = true;
+        self.update_ui = true;
+        ctx.redraw = true;
+    }
+}
+
+impl Scene for GameScene {
+    fn handle_event(&mut self, ctx: &mut Context, event: &Event) -> Result<Transition, String> {
+        // The field can't be played while it zooms in
+        if self.zoom.is_some() {
+            return Ok(Transition::Stay);
+        }
+        self.mesh.bind();
+        let (w, h) = (self.w, self.h);
+        let (aspect, window_px_size) = (ctx.aspect, ctx.window_px_size);
+        let bindings = &ctx.bindings;
+        let replaying = self.replaying;
+        match *event {
+            // The fingers are handled on their own, not through the mouse SDL makes up
+            Event::MouseButtonDown {
+                which: TOUCH_MOUSE_ID,
+                ..
+            }
+            | Event::MouseButtonUp {
+                which: TOUCH_MOUSE_ID,
+                ..
+            }
+            | Event::MouseMotion {
+                which: TOUCH_MOUSE_ID,
+                ..
+            }
+            | Event::MouseWheel {
+                which: TOUCH_MOUSE_ID,
+                ..
+            } => {}
+            // When a button that digs or pans gets pressed keep where and when,
+            // to tell the clicks from the drags
+            Event::MouseButtonDown {
+                mouse_btn, x, y, ..
+            } => {
+                let input = Input::Mouse(mouse_btn);
+                if (bindings.is(input, Command::Dig) || bindings.is(input, Command::Pan))
+                    && self.dig_button.is_none()
+                    && self.bomb.is_none()
+                {
+                    self.dig_button = Some((mouse_btn, x, y, Instant::now()));
+                    self.chording |= self.flag_button;
+                } else if bindings.is(input, Command::Flag) {
+                    self.flag_button = true;
+                    self.chording |= self.dig_button.is_some();
+                }
+            }
+            // When a mouse button gets released
+            Event::MouseButtonUp {
+                mouse_btn, x, y, ..
+            } => {
+                let input = Input::Mouse(mouse_btn);
+                let pressed = self.dig_button.filter(|&(button, ..)| button == mouse_btn);
+                // If it's one of the two buttons pressed together
+                // chord when the first one gets released
+                if self.chording && (pressed.is_some() || bindings.is(input, Command::Flag)) {
+                    if self.dig_button.is_some()
+                        && self.flag_button
+                        && !self.block_click
+                        && !replaying
+                    {
+                        if let Some(c) = self.cursor {
+                            self.actions
+                                .extend(tile_action(&self.field, c, Command::Chord));
+                        }
+                    }
+                    if pressed.is_some() {
+                        self.dig_button = None;
+                        self.dragging = false;
+                    } else {
+                        self.flag_button = false;
+                    }
+                    self.chording = self.dig_button.is_some() || self.flag_button;
+                } else if let Some(t) = pressed {
+                    // Play again on a new field when the restart button gets clicked
+                    if !self.dragging
+                        && !replaying
+                        && on_restart_button(x, y, aspect, window_px_size)
+                    {
+                        return self.restart(ctx);
+                    }
+                    // If it was a click dig the tile, the buttons that can't pan always click
+                    let click = t.3.elapsed() <= bindings.click_threshold
+                        || !bindings.is(input, Command::Pan);
+                    if click && bindings.is(input, Command::Dig) && !self.block_click && !replaying
+                    {
+                        if let Some(c) = self.cursor {
+                            self.actions
+                                .extend(tile_action(&self.field, c, Command::Dig));
+                        }
+                    } else if self.hovering_next {
+                        return self.finish(ctx, false, false);
+                    }
+                    self.dig_button = None;
+                    self.dragging = false;
+                } else if bindings.is(input, Command::Flag) {
+                    if !self.block_click && !self.init && !replaying {
+                        if let Some(c) = self.cursor {
+                            self.actions
+                                .extend(tile_action(&self.field, c, Command::Flag));
+                        }
+                    }
+                    self.flag_button = false;
+                } else if bindings.is(input, Command::Chord)
+                    && !self.block_click
+                    && !self.init
+                    && !replaying
+                {
+                    if let Some(c) = self.cursor {
+                        self.actions
+                            .extend(tile_action(&self.field, c, Command::Chord));
+                    }
+                }
+            }
+            // When the mouse is moved
+            Event::MouseMotion {
+                xrel, yrel, x, y, ..
+            } => {
+                if self.dragging {
+                    // Add the movement to the offset
+                    self.offset.0 += xrel as f32 * window_px_size.0 / self.scale;
+                    self.offset.1 += -yrel as f32 * window_px_size.1 / self.scale;
+
+                    // Limit the offset inside the field
+                    self.clamp_offset();
+                    ctx.redraw = true;
+                } else if let Some(t) = self
+                    .dig_button
+                    .filter(|&(button, ..)| bindings.is(Input::Mouse(button), Command::Pan))
+                {
+                    // If the mouse moved while clicking it is dragging
+                    let threshold = bindings.drag_threshold;
+                    if (t.1 - x).abs() > threshold || (t.2 - y).abs() > threshold {
+                        self.dragging = true;
+                        // Calculate the movement amount relative to OpenGL coordinates
+                        self.offset.0 += (x - t.1) as f32 * window_px_size.0 / self.scale;
+                        self.offset.1 += -(y - t.2) as f32 * window_px_size.1 / self.scale;
+
+                        ctx.redraw = true;
+                    }
+                // Prevent the user from clicking when a bomb has been digged,
+                // during a replay the cursor is moved by the actions instead
+                } else if !self.block_click && !replaying {
+                    let tile = tile_at(
+                        x,
+                        y,
+                        self.offset,
+                        self.scale,
+                        aspect,
+                        window_px_size,
+                        (w, h),
+                    );
+                    // Limit the cursor inside the mine field
+                    if tile.is_none() {
+                        if self.cursor.is_some() {
+                            self.cursor = None;
+                            // Hide the cursor behind the selected one
+                            let (sx, sy) = (self.selected.0 as f32, self.selected.1 as f32);
+                            put_unit_square(&mut self.ui_data, 6, sx, sy);
+                            VBO::write(self.ui_offset + 6, &self.ground_data[6..12]);
+                            ctx.redraw = true;
+                        }
+                    }
+                    // If the cursor changed position
+                    else if self.cursor != tile {
+                        self.cursor = tile;
+                        // Place the cursor in the new place
+                        if let Some((xx, yy)) = tile {
+                            self.actions.push(Action::Cursor(xx - 1, yy - 1));
+                        }
+                    }
+                } else if self.block_click {
+                    let theme = &ctx.theme;
+                    if on_next_button(x, y, aspect, window_px_size) {
+                        if !self.hovering_next {
+                            put_rect(&mut self.ui_data, 6, 0.0, 0.5, 2.0, 0.5);
+                            let next = theme.atlas.ui.next;
+                            apply_sprite(&mut self.ui_data, 6, &theme.ui, next);
+                            self.hovering_next = true;
+                            ctx.redraw = true;
+                        }
+                    } else if self.hovering_next {
+                        self.hovering_next = false;
+                        ctx.redraw = true;
+                        put_rect(&mut self.ui_data, 6, 0.0, 0.75, 2.0, 0.25);
+                        let edge = theme.atlas.ui.page_edge;
+                        apply_sprite(&mut self.ui_data, 6, &theme.ui, edge);
+                    }
+                }
+            }
+            // When scrolling move the field a tile at a time if the wheel pans
+            Event::MouseWheel { x, y, .. }
+                if !self.dragging
+                    && self.bomb.is_none()
+                    && bindings.is(Input::Wheel, Command::Pan) =>
+            {
+                self.offset.0 -= x as f32;
+                self.offset.1 -= y as f32;
+                self.clamp_offset();
+                ctx.redraw = true;
+            }
+            // Otherwise scale up or down based on the scrolling direction
+            Event::MouseWheel { y, .. } if !self.dragging && self.bomb.is_none() => {
+                let y = if bindings.is(Input::Wheel, Command::ZoomIn) {
+                    y
+                } else if bindings.is(Input::Wheel, Command::ZoomOut) {
+                    -y
+                } else {
+                    0
+                };
+                // Limit the scale between 'max_scale' and 1
+                self.scale = (self.scale * (1.0 + 0.1 * y as f32))
+                    .min(1.0)
+                    .max(self.max_scale);
+                ctx.redraw = true;
+            }
+            // The first finger points at its tile, the others make a pinch out of it
+            Event::FingerDown {
+                finger_id, x, y, ..
+            } => {
+                self.fingers += 1;
+                self.pinch = None;
+                if let Some(t) = &mut self.touch {
+                    t.done = true;
+                } else {
+                    let (width, height) = ctx.window_size;
+                    let (x, y) = ((x * width as f32) as i32, (y * height as f32) as i32);
+                    self.touch = Some(Touch::new(finger_id, (x, y)));
+                    if !self.block_click && !replaying {
+                        self.cursor = tile_at(
+                            x,
+                            y,
+                            self.offset,
+                            self.scale,
+                            aspect,
+                            window_px_size,
+                            (w, h),
+                        );
+                        if let Some((xx, yy)) = self.cursor {
+                            self.actions.push(Action::Cursor(xx - 1, yy - 1));
+                        }
+                    }
+                }
+            }
+            // Moving the finger away from where it went down makes it neither tap
+            // nor long press
+            Event::FingerMotion {
+                finger_id, x, y, ..
+            } => {
+                if let Some(t) = self.touch.as_mut().filter(|t| t.finger == finger_id) {
+                    let (width, height) = ctx.window_size;
+                    let threshold = bindings.drag_threshold;
+                    if ((x * width as f32) as i32 - t.start.0).abs() > threshold
+                        || ((y * height as f32) as i32 - t.start.1).abs() > threshold
+                    {
+                        t.done = true;
+                    }
+                }
+            }
+            // Lifting the finger before the long press taps like clicking a button that digs
+            Event::FingerUp { finger_id, .. } => {
+                self.fingers = self.fingers.saturating_sub(1);
+                self.pinch = None;
+                if let Some(t) = self.touch.take() {
+                    if t.finger != finger_id {
+                        self.touch = Some(t);
+                    } else if !t.done {
+                        let (x, y) = t.start;
+                        if self.block_click && on_next_button(x, y, aspect, window_px_size) {
+                            return self.finish(ctx, false, false);
+                        }
+                        if !replaying && on_restart_button(x, y, aspect, window_px_size) {
+                            return self.restart(ctx);
+                        }
+                        if let (Some(c), false, false) = (self.cursor, self.block_click, replaying)
+                        {
+                            self.actions
+                                .extend(tile_action(&self.field, c, Command::Dig));
+                        }
+                    }
+                }
+                // Put the cursor back to its size after the long press feedback
+                if let (Some((xx, yy)), false) = (self.cursor, self.block_click) {
+                    put_unit_square(&mut self.ui_data, 6, xx as f32, yy as f32);
+                    self.update_ui = true;
+                    ctx.redraw = true;
+                }
+            }
+            // Two fingers pan the field as they move and zoom around their centre
+            // as they pinch
+            Event::MultiGesture {
+                d_dist,
+                x,
+                y,
+                num_fingers,
+                ..
+            } if num_fingers >= 2 && self.bomb.is_none() => {
+                if let Some(t) = &mut self.touch {
+                    t.done = true;
+                }
+                let (width, height) = ctx.window_size;
+                let centre = (
+                    x * width as f32 * window_px_size.0 - 1.0 / aspect.0,
+                    1.0 / aspect.1 - y * height as f32 * window_px_size.1,
+                );
+                if let Some(last) = self.pinch {
+                    self.offset.0 += (centre.0 - last.0) / self.scale;
+                    self.offset.1 += (centre.1 - last.1) / self.scale;
+                }
+                // Keep the tile at the centre of the pinch where it is
+                let zoomed = (self.scale * (1.0 + d_dist * PINCH_ZOOM))
+                    .min(1.0)
+                    .max(self.max_scale);
+                self.offset.0 += centre.0 / zoomed - centre.0 / self.scale;
+                self.offset.1 += centre.1 / zoomed - centre.1 / self.scale;
+                self.scale = zoomed;
+                self.clamp_offset();
+                self.pinch = Some(centre);
+                ctx.redraw = true;
+            }
+            // Play again on a new field
+            _ if !replaying && bindings.pressed(event, Command::Restart) => {
+                return self.restart(ctx);
+            }
+            // Leave the replay for the menu, which mustn't start it again
+            _ if replaying && bindings.pressed(event, Command::Menu) => {
+                ctx.playback = None;
+                return self.finish(ctx, false, true);
+            }
+            // Pause the game, from the pause menu it can be left for the menu
+            _ if !replaying
+                && (bindings.pressed(event, Command::Pause)
+                    || bindings.pressed(event, Command::Menu)) =>
+            {
+                return self.pause(ctx);
+            }
+            Event::Window {
+                win_event: WindowEvent::FocusLost,
+                ..
+            } if !replaying => return self.pause(ctx),
+            // Go on once a bomb has been digged, like clicking next
+            Event::KeyDown { .. } | Event::ControllerButtonDown { .. }
+                if self.block_click && bindings.confirms(event) =>
+            {
+                return self.finish(ctx, false, false);
+            }
+            // Control the replay: pause, speed and seek, even back from the explosion
+            Event::KeyDown { .. } | Event::ControllerButtonDown { .. }
+                if !self.block_click || replaying =>
+            {
+                if let Some(replay) = &ctx.playback {
+                    if bindings.pressed(event, Command::Pause) {
+                        self.replay_paused = !self.replay_paused;
+                    }
+                    match bindings.direction(event) {
+                        Some((0, 1)) => self.replay_speed = (self.replay_speed * 2.0).min(16.0),
+                        Some((0, -1)) => self.replay_speed = (self.replay_speed / 2.0).max(0.25),
+                        Some((1, 0)) => {
+                            self.replay_time = (self.replay_time + SEEK_STEP).min(replay.duration())
+                        }
+                        Some((-1, 0)) => {
+                            // Go back by rebuilding the field from the beginning
+                            self.replay_time =
+                                self.replay_time.checked_sub(SEEK_STEP).unwrap_or_default();
+                            let replay_time = self.replay_time;
+                            self.next_action = replay
+                                .actions
+                                .iter()
+                                .take_while(|&&(time, _)| time <= replay_time)
+                                .count();
+                            self.field = replay.field_at(replay_time);
+                            self.flags = flagged_tiles(&self.field);
+                            if self.block_click {
+                                self.undo_explosion(ctx);
+                            }
+                            show_field(
+                                &mut self.ground_data,
+                                &mut self.props_data,
+                                &self.field,
+                                &ctx.theme,
+                                &mut ctx.rng,
+                                &self.tile_distr,
+                            );
+                            self.update_ground = true;
+                            self.update_props = true;
+                        }
+                        _ => {}
+                    }
+                    ctx.redraw = true;
+                // Play with the keyboard or a controller on the selected tile
+                } else {
+                    self.step = self.step.or(bindings.direction(event));
+                    for &command in &[Command::Dig, Command::Flag, Command::Chord] {
+                        if bindings.pressed(event, command)
+                            && !(self.init && command == Command::Flag)
+                        {
+                            self.actions
+                                .extend(tile_action(&self.field, self.selected, command));
+                        }
+                    }
+                    if bindings.pressed(event, Command::ZoomIn) {
+                        self.scale = (self.scale * 1.1).min(1.0);
+                        follow_tile(&mut self.offset, self.selected, self.scale, aspect);
+                        ctx.redraw = true;
+                    }
+                    if bindings.pressed(event, Command::ZoomOut) {
+                        self.scale = (self.scale * 0.9).max(self.max_scale);
+                        ctx.redraw = true;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn update(&mut self, ctx: &mut Context, dt: Duration) -> Result<Transition, String> {
+        self.mesh.bind();
+        // Zoom in on the field before it can be played
+        if let Some(zoom) = &mut self.zoom {
+            let elapsed = zoom.advance(dt);
+            self.scale = (elapsed * FRAC_PI_2).sin() * self.max_scale + 0.001;
+            if self.scale >= self.max_scale {
+                self.scale = self.max_scale;
+                self.zoom = None;
+            }
+            ctx.redraw = true;
+            return Ok(Transition::Stay);
+        }
+        let (w, h) = (self.w, self.h);
+        let aspect = ctx.aspect;
+        // Take the actions of the replay that are due
+        if let Some(replay) = &ctx.playback {
+            if !self.replay_paused && !self.block_click {
+                self.replay_time += dt.mul_f32(self.replay_speed);
+                ctx.redraw = true;
+            }
+            while let Some(&(time, action)) = replay.actions.get(self.next_action) {
+                if time > self.replay_time {
+                    break;
+                }
+                self.actions.push(action);
+                self.next_action += 1;
+            }
+        }
+        if let Some(transition) = self.apply_actions(ctx)? {
+            return Ok(transition);
+        }
+        // Move the selected tile with the keys, the d-pad or the left stick
+        if !self.block_click && ctx.playback.is_none() {
+            let stick = ctx.gamepads.stick(Axis::LeftX, Axis::LeftY);
+            self.step = self.step.or(self.left_stick.update(stick, Instant::now()));
+        }
+        if let Some((dx, dy)) = self.step.take() {
+            self.selected = (
+                (self.selected.0 as i32 + dx).clamp(1, w as i32) as usize,
+                (self.selected.1 as i32 + dy).clamp(1, h as i32) as usize,
+            );
+            let (sx, sy) = (self.selected.0 as f32, self.selected.1 as f32);
+            put_unit_square(&mut self.ui_data, 0, sx, sy);
+            // The pointed tile follows, so that the replays show the moves
+            self.cursor = Some(self.selected);
+            self.actions
+                .push(Action::Cursor(self.selected.0 - 1, self.selected.1 - 1));
+            follow_tile(&mut self.offset, self.selected, self.scale, aspect);
+            self.update_ui = true;
+            ctx.redraw = true;
+        }
+        // A finger held still on a tile flags it, the cursor shrinks until it does
+        let fingers = self.fingers;
+        if let Some(t) = self.touch.as_mut().filter(|t| !t.done && fingers == 1) {
+            if let (Some((xx, yy)), false, false, false) =
+                (self.cursor, self.block_click, self.replaying, self.init)
+            {
+                let hold = t.hold();
+                if hold < 1.0 {
+                    let size = 1.0 - 0.4 * hold;
+                    let margin = (1.0 - size) / 2.0;
+                    put_rect(
+                        &mut self.ui_data,
+                        6,
+                        xx as f32 + margin,
+                        yy as f32 + margin,
+                        size,
+                        size,
+                    );
+                } else {
+                    t.done = true;
+                    self.actions
+                        .extend(tile_action(&self.field, (xx, yy), Command::Flag));
+                    put_unit_square(&mut self.ui_data, 6, xx as f32, yy as f32);
+                }
+                self.update_ui = true;
+                ctx.redraw = true;
+            }
+        }
+        // Pan with the right stick and zoom with the triggers
+        let frame_time = dt.as_secs_f32();
+        if !self.dragging && self.bomb.is_none() {
+            let (x, y) = ctx.gamepads.stick(Axis::RightX, Axis::RightY);
+            if x != 0.0 || y != 0.0 {
+                let speed = PAD_PAN_SPEED * frame_time / self.scale;
+                self.offset.0 -= x * speed / aspect.0;
+                self.offset.1 -= y * speed / aspect.1;
+                self.clamp_offset();
+                ctx.redraw = true;
+            }
+            let zoom =
+                ctx.gamepads.trigger(Axis::TriggerRight) - ctx.gamepads.trigger(Axis::TriggerLeft);
+            if zoom != 0.0 {
+                self.scale = (self.scale * (1.0 + zoom * PAD_ZOOM_SPEED * frame_time))
+                    .min(1.0)
+                    .max(self.max_scale);
+                ctx.redraw = true;
+            }
+        }
+        // Check if the the user is dragging
+        if !self.dragging {
+            if let Some((button, _, _, t)) = self.dig_button {
+                if t.elapsed() > ctx.bindings.click_threshold
+                    && ctx.bindings.is(Input::Mouse(button), Command::Pan)
+                {
+                    self.dragging = true;
+                }
+            }
+        }
+        self.follow_bomb(ctx, dt)?;
+        // If a tick has passed
+        self.tick += dt;
+        if self.tick > ctx.settings.game.tick_delay() {
+            self.tick = Duration::from_secs(0);
+            self.play_tick(ctx);
+        }
+        if self.update_ground {
+            self.update_ground = false;
+            VBO::write(0, &self.ground_data);
+        }
+        if self.update_props {
+            self.update_props = false;
+            VBO::write(self.props_offset, &self.props_data);
+        }
+        if self.update_ui {
+            self.update_ui = false;
+            VBO::write(self.ui_offset, &self.ui_data);
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        self.mesh.bind();
+        ctx.view(self.offset, self.scale);
+        if self.zoom.is_some() {
+            scene::draw(0, self.hud_offset);
+            return;
+        }
+        if self.bomb_stage == 3 {
+            scene::draw(0, self.ui_offset + 6);
+            ctx.view((-1.0, 0.0), 1.0);
+            scene::draw(self.ui_offset + 6, 6);
+        } else {
+            scene::draw(0, self.hud_offset);
+        }
+        // The time starts with the first dig and stops when the game is over
+        let time = if self.replaying {
+            self.replay_time
+        } else {
+            self.game_time.unwrap_or_else(|| self.playing_time())
+        };
+        put_hud(
+            &mut self.hud_data,
+            ctx.aspect,
+            self.field.bombs() as i64 - flagged_tiles(&self.field).len() as i64,
+            time.as_secs(),
+            self.field.phase(),
+            !self.replaying,
+            &ctx.theme,
+        );
+        VBO::write(self.hud_offset, &self.hud_data);
+        // Draw the hud anchored to the window, whatever the zoom and position of the field
+        ctx.view((0.0, 0.0), 1.0);
+        scene::draw(self.hud_offset, HUD_SIZE);
+        // Show how the replay is being played in the top left corner
+        if self.replaying {
+            let status = format!(
+                "REPLAY {}x{}",
+                self.replay_speed,
+                if self.replay_paused { " PAUSED" } else { "" }
+            );
+            let mut text_data = ctx.theme.font.layout(
+                &status,
+                PX * 2.0 - 1.0 / ctx.aspect.0,
+                1.0 / ctx.aspect.1 - PX * 2.0,
+                PX,
+                Align::Left,
+            );
+            text_data.truncate(TEXT_SIZE);
+            VBO::write(self.text_offset, &text_data);
+            text::set_color(ctx.tint_loc, INK);
+            scene::draw(self.text_offset, text_data.len());
+            text::set_color(ctx.tint_loc, WHITE);
+        }
+    }
+
+    fn resized(&mut self, ctx: &mut Context) {
+        // Keep the whole field visible when zoomed out
+        self.max_scale = fit_scale(self.w, self.h, ctx.aspect);
+        if self.zoom.is_none() && self.scale < self.max_scale {
+            self.scale = self.max_scale;
+        }
+    }
+
+    fn resumed(&mut self, ctx: &mut Context) -> Result<Transition, String> {
+        if self.theme != ctx.settings.game.theme {
+            self.retexture(ctx);
+        }
+        match ctx.paused.take() {
+            Some(PauseChoice::RestartBoard) => {
+                self.replay_board(ctx);
+                self.restart(ctx)
+            }
+            Some(PauseChoice::NewGame) => self.restart(ctx),
+            // Go back to the menu, where the game in progress can be continued
+            Some(PauseChoice::MainMenu) => {
+                self.save_game()?;
+                self.finish(ctx, false, true)
+            }
+            Some(PauseChoice::Resume) | None => {
+                // The clock goes on from where it stopped
+                if self.clock_paused {
+                    self.clock_paused = false;
+                    self.playing_since = Some(Instant::now());
+                }
+                Ok(Transition::Stay)
+            }
+        }
+    }
+
+    fn quit(&mut self, _ctx: &mut Context) -> Result<(), String> {
+        // The game can be over while the view still follows the bombs
+        if let (Some(replay), true) = (&self.recording, self.field.is_over()) {
+            save_replay(replay)?;
+        }
+        self.save_game()
+    }
+}
+
+// The page telling whether the game has been won or lost, coming down over the field
+pub struct ResultScene {
+    mesh: Mesh,
+    // The ground and the props of the field, then the page and the word on it,
+    // then the text written above the word
+    page: Vec<Vertex>,
+    field_size: usize,
+    text: String,
+    text_size: usize,
+    offset: (f32, f32),
+    scale: f32,
+    // Time since the page started coming down, the scene lasts `RESULT_TIME`
+    time: Duration,
+    // The page coming down in the first second and going back up in the third one
+    down: Animation,
+    up: Animation,
+    // Once a replay is over there is nothing else to do
+    replaying: bool,
+}
+
+impl ResultScene {
+    pub fn new(
+        ctx: &Context,
+        field: Vec<Vertex>,
+        offset: (f32, f32),
+        scale: f32,
+        won: bool,
+        replaying: bool,
+        text: String,
+    ) -> Result<ResultScene, String> {
+        let theme = &ctx.theme;
+        let aspect = ctx.aspect;
+        let animations = ctx.settings.game.animations;
+        // Setup the page
+        let mut page = vec![Default::default(); 12];
+        put_rect(&mut page, 0, 0.0, aspect.1 - 0.25, 2.0, 2.0);
+        apply_sprite(&mut page, 0, &theme.ui, theme.atlas.ui.menu);
+        put_rect(
+            &mut page,
+            6,
+            44.0 * PX,
+            aspect.1 - 0.25 + 12.0 * PX,
+            PX * 48.0,
+            PX * 16.0,
+        );
+        let word = if won {
+            theme.atlas.ui.won
+        } else {
+            theme.atlas.ui.lost
+        };
+        apply_sprite(&mut page, 6, &theme.ui, word);
+        rotate_tecture_rect(&mut page, 6);
+
+        let text_size = Font::quads(&text) * 6;
+        let mesh = Mesh::new(&ctx.program, field.len() + page.len() + text_size)?;
+        VBO::write(0, &field);
+        VBO::write(field.len(), &page);
+        Ok(ResultScene {
+            mesh,
+            page,
+            field_size: field.len(),
+            text,
+            text_size,
+            offset,
+            scale,
+            time: Duration::from_secs(0),
+            down: Animation::new(animations, Duration::from_secs(1)),
+            up: Animation::new(animations, Duration::from_secs(1)),
+            replaying,
+        })
+    }
+}
+
+impl Scene for ResultScene {
+    fn handle_event(&mut self, _ctx: &mut Context, _event: &Event) -> Result<Transition, String> {
+        Ok(Transition::Stay)
+    }
+
+    fn update(&mut self, ctx: &mut Context, dt: Duration) -> Result<Transition, String> {
+        self.mesh.bind();
+        self.time += dt;
+        let aspect = ctx.aspect;
+        // Move the page down, wait a second, move it back up and wait another second
+        let (page, word) = if self.time < Duration::from_secs(2) {
+            let elapsed = self.down.advance(dt);
+            (
+                mix(aspect.1 - 0.25, -1.0, elapsed),
+                mix(aspect.1 - 0.25 + 12.0 * PX, -52.0 * PX, elapsed),
+            )
+        } else {
+            let elapsed = self.up.advance(dt);
+            (
+                mix(-1.0, aspect.1, elapsed),
+                mix(-52.0 * PX, 12.0 * PX + aspect.1, elapsed),
+            )
+        };
+        move_rect(&mut self.page, 0, None, Some((page, 2.0)));
+        move_rect(&mut self.page, 6, None, Some((word, PX * 16.0)));
+        VBO::write(self.field_size, &self.page);
+        // The text follows the page, centred above the word
+        let (font, scale) = (&ctx.theme.font, PX * 0.5);
+        let lines = self.text.split('\n').count() as f32;
+        let top = word + PX * 20.0 + lines * font.line_height() as f32 * scale;
+        let text = font.layout(&self.text, PX * 68.0, top, scale, Align::Center);
+        VBO::write(self.field_size + self.page.len(), &text);
+        ctx.redraw = true;
+
+        if self.time < RESULT_TIME {
+            Ok(Transition::Stay)
+        } else if self.replaying {
+            Ok(Transition::Quit)
+        } else {
+            Ok(Transition::Replace(Box::new(MenuScene::new(ctx)?)))
+        }
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        self.mesh.bind();
+        // The field shrinks away as the page comes down
+        let down = self.down.progress();
+        if down < 1.0 {
+            ctx.view(self.offset, self.scale * (1.0 - down));
+            scene::draw(0, self.field_size);
+        }
+        ctx.view((-1.0, 0.0), 1.0);
+        scene::draw(self.field_size, self.page.len());
+        text::set_color(ctx.tint_loc, INK);
+        scene::draw(self.field_size + self.page.len(), self.text_size);
+        text::set_color(ctx.tint_loc, WHITE);
+    }
+}
+
+// Probability: 1/2^n
+fn random_bool(probability: u32) -> bool {
+    let mut res = true;
+    for _ in 0..probability {
+        res = res && rand::random();
+    }
+    res
+}
+
+fn update_borders(data: &mut Vec<Vertex>, flags: &mut [bool], w: usize, h: usize, size: f32) {
+    let mut border_index;
+    let mut index;
+    for y in 1..h + 1 {
+        // Left border
+        if flags[y] {
+            index = y * (w + 2) * 6;
+            flags[y] = !advance_frame(data, index, size);
+        } else {
+            flags[y] = random_bool(3);
+        }
+
+        // Right border
+        border_index = h + w + 3 + y;
+        if flags[border_index] {
+            index = (y * (w + 2) + w + 1) * 6;
+            flags[border_index] = !advance_frame(data, index, size);
+        } else {
+            flags[border_index] = random_bool(3);
+        }
+    }
+    for x in 1..w + 1 {
+        // Lower border
+        border_index = 2 * h + 3 + w + x;
+        if flags[border_index] {
+            index = x * 6;
+            flags[border_index] = !advance_frame(data, index, size);
+        } else {
+            flags[border_index] = random_bool(3);
+        }
+
+        // Upper border
+        border_index = h + 1 + x;
+        if flags[border_index] {
+            index = ((h + 1) * (w + 2) + x) * 6;
+            flags[border_index] = !advance_frame(data, index, size);
+        } else {
+            flags[border_index] = random_bool(3);
+        }
+    }
+    // Lower left border corner
+    if flags[0] {
+        flags[0] = !advance_frame(data, 0, size);
+    } else {
+        flags[0] = random_bool(3);
+    }
+    // Upper left border corner
+    border_index = h + 1;
+    if flags[border_index] {
+        index = (h + 1) * (w + 2) * 6;
+        flags[border_index] = !advance_frame(data, index, size);
+    } else {
+        flags[border_index] = random_bool(3);
+    }
+    // Upper right border corner
+    border_index = 2 + h + w;
+    if flags[border_index] {
+        index = ((h + 1) * (w + 2) + w + 1) * 6;
+        flags[border_index] = !advance_frame(data, index, size);
+    } else {
+        flags[border_index] = random_bool(3);
+    }
+    // Lower right border corner
+    border_index = 3 + 2 * h + w;
+    if flags[border_index] {
+        index = (w + 1) * 6;
+        flags[border_index] = !advance_frame(data, index, size);
+    } else {
+        flags[border_index] = random_bool(3);
+    }
+}
+
+fn setup_ground(
+    data: &mut Vec<Vertex>,
+    w: usize,
+    h: usize,
+    theme: &Theme,
+    rng: &mut ThreadRng,
+    distr: &Uniform<u8>,
+) {
+    let (sheet, atlas) = (&theme.ground, &theme.atlas.ground);
+    let mut index;
+    for y in 1..h + 1 {
+        for x in 1..w + 1 {
+            index = (y * (w + 2) + x) * 6; // get the 1D index of a 2D array
+            put_unit_square(data, index, x as f32, y as f32);
+            // Generate a rando variation for each tile
+            apply_tile(data, index, sheet, atlas.sand, distr.sample(rng) as u32);
+        }
+        // Set up the vertical borders
+        // Left border
+        index = y * (w + 2) * 6;
+        put_unit_square(data, index, 0.0, y as f32);
+        apply_tile(data, index, sheet, atlas.left, 0);
+        // Right border
+        index = (y * (w + 2) + w + 1) * 6;
+        put_unit_square(data, index, (w + 1) as f32, y as f32);
+        apply_tile(data, index, sheet, atlas.right, 0);
+    }
+    // Set up the horizontal borders
+    for x in 1..w + 1 {
+        // Lower border
+        index = x * 6;
+        put_unit_square(data, index, x as f32, 0.0);
+        apply_tile(data, index, sheet, atlas.bottom, 0);
+        // Upper border
+        index = ((h + 1) * (w + 2) + x) * 6;
+        put_unit_square(data, index, x as f32, (h + 1) as f32);
+        apply_tile(data, index, sheet, atlas.top, 0);
+    }
+    // Lower left border corner
+    put_unit_square(data, 0, 0.0, 0.0);
+    apply_tile(data, 0, sheet, atlas.lower_left, 0);
+    // Upper left border corner
+    index = (h + 1) * (w + 2) * 6;
+    put_unit_square(data, index, 0.0, (h + 1) as f32);
+    apply_tile(data, index, sheet, atlas.upper_left, 0);
+    // Upper right border corner
+    index = ((h + 1) * (w + 2) + w + 1) * 6;
+    put_unit_square(data, index, (w + 1) as f32, (h + 1) as f32);
+    apply_tile(data, index, sheet, atlas.upper_right, 0);
+    // Lower right border corner
+    index = (w + 1) * 6;
+    put_unit_square(data, index, (w + 1) as f32, 0.0);
+    apply_tile(data, index, sheet, atlas.lower_right, 0);
+}
+
+// Returns the biggest scale at which the whole field, border included, fits the window
+fn fit_scale(w: usize, h: usize, aspect: (f32, f32)) -> f32 {
+    let x = 2.0 / aspect.0 / (w + 2) as f32;
+    let y = 2.0 / aspect.1 / (h + 2) as f32;
+    if x < y {
+        x
+    } else {
+        y
+    }
+}
+
+// Puts the hud along the bottom edge of the window: the bombs left minus the flags on the left,
+// the time on the right and the restart button, showing how the game is going, in the middle
+fn put_hud(
+    vec: &mut Vec<Vertex>,
+    aspect: (f32, f32),
+    bombs_left: i64,
+    seconds: u64,
+    phase: GamePhase,
+    restart: bool,
+    theme: &Theme,
+) {
+    let (ui, atlas) = (&theme.ui, &theme.atlas.ui);
+    let y = PX * 2.0 - 1.0 / aspect.1;
+    let panels = [
+        (PX * 2.0 - 1.0 / aspect.0, atlas.mines_icon),
+        (1.0 / aspect.0 - PX * 31.0, atlas.clock_icon),
+    ];
+    for (i, &(x, icon)) in panels.iter().enumerate() {
+        let index = i * 36;
+        put_rect(vec, index, x, y, PX * 29.0, PX * 11.0);
+        apply_sprite(vec, index, ui, atlas.hud_panel);
+        put_rect(
+            vec,
+            index + 6,
+            x + PX * 2.0,
+            y + PX * 2.0,
+            PX * 7.0,
+            PX * 7.0,
+        );
+        apply_sprite(vec, index + 6, ui, icon);
+    }
+    let (x, y) = (panels[0].0 + PX * 11.0, y + PX * 2.0);
+    let bombs = bombs_left.unsigned_abs().min(999) as usize;
+    put_number(vec, 12, 4, bombs, x, y, theme);
+    if bombs_left < 0 {
+        // Put the minus right before the first digit
+        let index = 3 - bombs.to_string().len();
+        let minus = 12 + index * 6;
+        let minus_x = x + PX * 4.0 * index as f32;
+        put_rect(vec, minus, minus_x, y, PX * 3.0, PX * 7.0);
+        apply_sprite(vec, minus, ui, atlas.minus);
+    }
+    let x = panels[1].0 + PX * 11.0;
+    put_number(vec, 48, 4, seconds.min(9999) as usize, x, y, theme);
+
+    if restart {
+        let face = match phase {
+            GamePhase::Won => 1,
+            GamePhase::Lost => 2,
+            _ => 0,
+        };
+        put_rect(vec, 72, PX * -5.5, y - PX * 2.0, PX * 11.0, PX * 11.0);
+        apply_sprite(vec, 72, ui, atlas.face(face));
+    } else {
+        reset_rect(vec, 72);
+    }
+}
+
+// Returns whether the window pixel at `x`, `y` is on the restart button of the hud
+fn on_restart_button(x: i32, y: i32, aspect: (f32, f32), window_px_size: (f32, f32)) -> bool {
+    let xx = x as f32 * window_px_size.0 - 1.0 / aspect.0;
+    let yy = 1.0 / aspect.1 - y as f32 * window_px_size.1;
+    let bottom = PX * 2.0 - 1.0 / aspect.1;
+    (PX * -5.5..PX * 5.5).contains(&xx) && (bottom..bottom + PX * 11.0).contains(&yy)
+}
+
+// Tells whether or not the pixel of the window at `x`, `y` is on the next button
+// shown after a bomb has been digged
+fn on_next_button(x: i32, y: i32, aspect: (f32, f32), window_px_size: (f32, f32)) -> bool {
+    let xx = x as f32 * window_px_size.0 - 1.0 / aspect.0;
+    let yy = 1.0 / aspect.1 - y as f32 * window_px_size.1;
+    yy > 0.75 && xx < 1.0 && xx > -1.0
+}
+
+// Returns the tile of a field of size `size` under the pixel of the window at `x`, `y`,
+// counting from 1 like the cursor
+fn tile_at(
+    x: i32,
+    y: i32,
+    offset: (f32, f32),
+    scale: f32,
+    aspect: (f32, f32),
+    window_px_size: (f32, f32),
+    size: (usize, usize),
+) -> Option<(usize, usize)> {
+    // Calculate the coordinate in OpenGL space and remove the decimal part
+    // (I can do this because I made each tile 1x1)
+    let xx = ((x as f32 * window_px_size.0 - 1.0 / aspect.0) / scale - offset.0) as usize;
+    let yy = ((1.0 / aspect.1 - y as f32 * window_px_size.1) / scale - offset.1) as usize;
+    if xx == 0 || xx > size.0 || yy == 0 || yy > size.1 {
+        None
+    } else {
+        Some((xx, yy))
+    }
+}
+
+// Returns what `command` does on the tile `tile` of `field`, counting from 1 like the cursor,
+// digging an already digged number chords it
+fn tile_action(field: &MineField, tile: (usize, usize), command: Command) -> Option<Action> {
+    let (x, y) = (tile.0 - 1, tile.1 - 1);
+    match command {
+        Command::Dig | Command::Chord if field.is_digged(x, y) => Some(Action::Chord(x, y)),
+        Command::Dig => Some(Action::Dig(x, y)),
+        Command::Flag => Some(Action::Flag(x, y)),
+        _ => None,
+    }
+}
+
+// Covers the tiles of the field of `w` by `h` with grass, only some of them get a variation
+fn setup_props(
+    data: &mut Vec<Vertex>,
+    w: usize,
+    h: usize,
+    theme: &Theme,
+    rng: &mut ThreadRng,
+    distr: &Uniform<u8>,
+) {
+    for y in 0..h {
+        for x in 0..w {
+            let index = (y * w + x) * 6;
+            put_unit_square(data, index, (x + 1) as f32, (y + 1) as f32);
+            // This if statement reduces the chances of verying the texture
+            // by changing it (on average) only once every four times
+            let row = if random_bool(2) {
+                distr.sample(rng) as u32
+            } else {
+                0
+            };
+            apply_tile(data, index, &theme.props, theme.atlas.props.grass, row);
+        }
+    }
+}
+
+// Returns the tiles of `field` that have a flag on top
+fn flagged_tiles(field: &MineField) -> Vec<(usize, usize)> {
+    let mut flags = Vec::new();
+    for x in 0..field.width() {
+        for y in 0..field.height() {
+            if field.is_flagged(x, y) {
+                flags.push((x, y));
+            }
+        }
+    }
+    flags
+}
+
+// Updates the ground and props textures of every tile to show the state of `field`,
+// which must not be over
+fn show_field(
+    ground: &mut Vec<Vertex>,
+    props: &mut Vec<Vertex>,
+    field: &MineField,
+    theme: &Theme,
+    rng: &mut ThreadRng,
+    distr: &Uniform<u8>,
+) {
+    let atlas = &theme.atlas.props;
+    let w = field.width();
+    for y in 0..field.height() {
+        for x in 0..w {
+            let index = (y * w + x) * 6;
+            let border = if field.is_digged(x, y) {
+                field.border_type(x, y)
+            } else {
+                0
+            };
+            move_texture_rect(
+                ground,
+                ((y + 1) * (w + 2) + x + 1) * 6,
+                Some(theme.ground.column(theme.atlas.ground.border(border))),
+                None,
+            );
+            if field.is_digged(x, y) {
+                move_texture_rect(
+                    props,
+                    index,
+                    Some(theme.props.column(atlas.number(field.bombs_near(x, y)))),
+                    Some(theme.props.row(distr.sample(rng) as u32)),
+                );
+            } else if field.is_flagged(x, y) {
+                move_texture_rect(
+                    props,
+                    index,
+                    Some(theme.props.column(atlas.flag)),
+                    Some(theme.props.row(0)),
+                );
+            } else {
+                move_texture_rect(props, index, Some(theme.props.column(atlas.grass)), None);
+            }
+        }
+    }
+}
+
+// Returns the statistics of the board and how well it has been played, as lines of text
+fn stats_lines(field: &MineField, clicks: usize, time: Duration) -> [String; 4] {
+    let board = field.stats();
+    let game = GameStats {
+        solved_bbbv: field.solved_bbbv(),
+        clicks,
+        time,
+    };
+    [
+        format!(
+            "3BV {}/{}  ZiNi {}",
+            game.solved_bbbv, board.bbbv, board.zini
+        ),
+        format!("openings {}  islands {}", board.openings, board.islands),
+        format!("time {:.3}s  clicks {}", time.as_secs_f64(), clicks),
+        format!(
+            "IOE {:.3}  3BV/s {:.3}",
+            game.efficiency(),
+            game.bbbv_per_second()
+        ),
+    ]
+}
+
+// Writes `replay` in the replays folder, naming it after its board id,
+// together with its RMV and MBF exports.
+// The replays of the same board are numbered so that none gets overwritten,
+// returns the name they have been given
+fn save_replay(replay: &Replay) -> Result<String, String> {
+    let dir = data_dir().join(REPLAY_DIR);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let name = (1..)
+        .map(|n| format!("{}-{}", replay.id, n))
+        .find(|name| {
+            ["txt", "rmv", "mbf"]
+                .iter()
+                .all(|ext| !dir.join(format!("{}.{}", name, ext)).exists())
+        })
+        .unwrap();
+    let path = dir.join(format!("{}.txt", name));
+    replay.save(&path)?;
+    rmv::save(replay, &path.with_extension("rmv"))?;
+    mbf::save(&replay.field, &path.with_extension("mbf"))?;
+    Ok(name)
+}
+
+// Moves the field as little as possible to have the tile at `tile` inside the window,
+// returns whether or not it had to be moved
+fn follow_tile(
+    offset: &mut (f32, f32),
+    tile: (usize, usize),
+    scale: f32,
+    aspect: (f32, f32),
+) -> bool {
+    // Half of the size of the window in tiles
+    let half = (1.0 / (aspect.0 * scale), 1.0 / (aspect.1 * scale));
+    let (x, y) = (tile.0 as f32, tile.1 as f32);
+    let moved = (
+        offset.0.min(half.0 - x - 1.0).max(-half.0 - x),
+        offset.1.min(half.1 - y - 1.0).max(-half.1 - y),
+    );
+    let changed = moved != *offset;
+    *offset = moved;
+    changed
+}
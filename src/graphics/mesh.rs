@@ -0,0 +1,47 @@
+use std::mem::size_of;
+
+use super::program::Program;
+use super::vao::VertexArrayObject as VAO;
+use super::vbo::VertexBufferObject as VBO;
+use super::vertex::Vertex;
+
+// A buffer of vertices together with the vertex array telling the shaders how to read it,
+// so that each scene can keep its own
+pub struct Mesh {
+    vao: VAO,
+    vbo: VBO,
+}
+
+impl Mesh {
+    // Allocates room for `size` vertices, the mesh is left bound
+    pub fn new(program: &Program, size: usize) -> Result<Mesh, String> {
+        let vao = VAO::new();
+        VAO::bind(&vao);
+        let vbo = VBO::new::<Vertex>(size, None);
+        VBO::attrib_format(
+            program.get_vertex_attrib("coord")?,
+            2,
+            size_of::<Vertex>(),
+            offset_of!(Vertex, coord),
+        );
+        VBO::attrib_format(
+            program.get_vertex_attrib("texture_coord")?,
+            2,
+            size_of::<Vertex>(),
+            offset_of!(Vertex, texture_coord),
+        );
+        VBO::integer_attrib_format(
+            program.get_vertex_attrib("texture_idx")?,
+            1,
+            size_of::<Vertex>(),
+            offset_of!(Vertex, texture_idx),
+        );
+        Ok(Mesh { vao, vbo })
+    }
+
+    // Makes it the buffer that `VBO::write` fills and the draw calls read
+    pub fn bind(&self) {
+        VAO::bind(&self.vao);
+        VBO::bind(&self.vbo);
+    }
+}
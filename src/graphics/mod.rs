@@ -1,4 +1,7 @@
+pub mod mesh;
 pub mod program;
+pub mod text;
 pub mod texture;
 pub mod vao;
 pub mod vbo;
+pub mod vertex;
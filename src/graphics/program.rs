@@ -1,7 +1,4 @@
 use std::ffi::CString;
-use std::fs::File;
-use std::io::Read;
-use std::path::Path;
 use std::ptr;
 
 use gl::types as gl_t;
@@ -52,106 +49,78 @@ impl Program {
         }
     }
 
-    pub fn new(path: &Path) -> Result<Program, String> {
+    // Compiles and links the program made of the shaders with the sources `vertex` and `fragment`
+    pub fn new(vertex: &str, fragment: &str) -> Result<Program, String> {
         unsafe {
             let program_id = gl::CreateProgram(); // Genereate the program ID
 
             let mut status: gl_t::GLint = 0;
-            let mut string = String::new();
 
             let mut len: gl_t::GLint = 0;
             let mut log: CString;
 
-            let vertex_shader;
-            if let Ok(mut file) = File::open(path.with_file_name("vertex.glsl")) {
-                // Open the file
-                if let Ok(_) = file.read_to_string(&mut string) {
-                    // Read the file
-                    println!("Vertex shader source:\n{}\n", string);
-
-                    // Create a new vertex shader
-                    vertex_shader = gl::CreateShader(gl::VERTEX_SHADER);
-                    // Attach the source code to it
-                    gl::ShaderSource(
-                        vertex_shader,
-                        1,
-                        &string_to_cstring(string.as_str()).as_ptr(),
-                        ptr::null(),
-                    );
-                    gl::CompileShader(vertex_shader); // Compile it
-                    gl::AttachShader(program_id, vertex_shader); // Attach it to the program
-
-                    // Checking shader compile status
-                    gl::GetShaderiv(vertex_shader, gl::COMPILE_STATUS, &mut status);
-                    println!("Vertex shader compile status: {}", status);
-
-                    if status == 0 {
-                        // Get the legth of the info log
-                        gl::GetShaderiv(vertex_shader, gl::INFO_LOG_LENGTH, &mut len);
-                        // Allocate the memory to store the log
-                        log = new_cstring_with_len(len as usize);
-                        // Retrive the info log
-                        gl::GetShaderInfoLog(vertex_shader, len, &mut len, log.as_ptr() as *mut _);
-                        println!(
-                            "Vertex shader info log: {}\n",
-                            log.into_string()
-                                .or(Err("Can't convert the vertex shader info log to a String"))?
-                        );
-                    }
-                } else {
-                    return Err("Cannot read the vertex shader file!".to_string());
-                }
-            } else {
-                return Err("Vertex shader not found!".to_string());
+            println!("Vertex shader source:\n{}\n", vertex);
+
+            // Create a new vertex shader
+            let vertex_shader = gl::CreateShader(gl::VERTEX_SHADER);
+            // Attach the source code to it
+            gl::ShaderSource(
+                vertex_shader,
+                1,
+                &string_to_cstring(vertex).as_ptr(),
+                ptr::null(),
+            );
+            gl::CompileShader(vertex_shader); // Compile it
+            gl::AttachShader(program_id, vertex_shader); // Attach it to the program
+
+            // Checking shader compile status
+            gl::GetShaderiv(vertex_shader, gl::COMPILE_STATUS, &mut status);
+            println!("Vertex shader compile status: {}", status);
+
+            if status == 0 {
+                // Get the legth of the info log
+                gl::GetShaderiv(vertex_shader, gl::INFO_LOG_LENGTH, &mut len);
+                // Allocate the memory to store the log
+                log = new_cstring_with_len(len as usize);
+                // Retrive the info log
+                gl::GetShaderInfoLog(vertex_shader, len, &mut len, log.as_ptr() as *mut _);
+                println!(
+                    "Vertex shader info log: {}\n",
+                    log.into_string()
+                        .or(Err("Can't convert the vertex shader info log to a String"))?
+                );
             }
-            let fragment_shader;
-            if let Ok(mut file) = File::open(path.with_file_name("fragment.glsl")) {
-                // Open the file
-                string.clear();
-                if let Ok(_) = file.read_to_string(&mut string) {
-                    // Read the file
-                    println!("Fragment shader source:\n{}\n", string);
-
-                    // Create a new fragment shader
-                    fragment_shader = gl::CreateShader(gl::FRAGMENT_SHADER);
-                    // Attach the source code to it
-                    gl::ShaderSource(
-                        fragment_shader,
-                        1,
-                        &string_to_cstring(string.as_str()).as_ptr(),
-                        ptr::null(),
-                    );
-                    gl::CompileShader(fragment_shader); // Compile it
-                    gl::AttachShader(program_id, fragment_shader); // Attach it to the program
-
-                    // Checking shader compile status
-                    gl::GetShaderiv(fragment_shader, gl::COMPILE_STATUS, &mut status);
-                    println!("Fragment shader compile status: {}", status);
-
-                    if status == 0 {
-                        // Get the legth of the info log
-                        gl::GetShaderiv(fragment_shader, gl::INFO_LOG_LENGTH, &mut len);
-                        // Allocate the memory to store the log
-                        log = new_cstring_with_len(len as usize);
-                        // Retrive the info log
-                        gl::GetShaderInfoLog(
-                            fragment_shader,
-                            len,
-                            &mut len,
-                            log.as_ptr() as *mut _,
-                        );
-                        println!(
-                            "Fragment shader info log: {}\n",
-                            log.into_string()
-                                .or(Err("Can't convert the fragment info log to a String"))?
-                        );
-                    }
-                // else shaders += FRAGMENT_SHADER;
-                } else {
-                    return Err("Cannot read the fragment shader file!".to_string());
-                }
-            } else {
-                return Err("Fragment shader not found!".to_string());
+
+            println!("Fragment shader source:\n{}\n", fragment);
+
+            // Create a new fragment shader
+            let fragment_shader = gl::CreateShader(gl::FRAGMENT_SHADER);
+            // Attach the source code to it
+            gl::ShaderSource(
+                fragment_shader,
+                1,
+                &string_to_cstring(fragment).as_ptr(),
+                ptr::null(),
+            );
+            gl::CompileShader(fragment_shader); // Compile it
+            gl::AttachShader(program_id, fragment_shader); // Attach it to the program
+
+            // Checking shader compile status
+            gl::GetShaderiv(fragment_shader, gl::COMPILE_STATUS, &mut status);
+            println!("Fragment shader compile status: {}", status);
+
+            if status == 0 {
+                // Get the legth of the info log
+                gl::GetShaderiv(fragment_shader, gl::INFO_LOG_LENGTH, &mut len);
+                // Allocate the memory to store the log
+                log = new_cstring_with_len(len as usize);
+                // Retrive the info log
+                gl::GetShaderInfoLog(fragment_shader, len, &mut len, log.as_ptr() as *mut _);
+                println!(
+                    "Fragment shader info log: {}\n",
+                    log.into_string()
+                        .or(Err("Can't convert the fragment info log to a String"))?
+                );
             }
             gl::LinkProgram(program_id);
 
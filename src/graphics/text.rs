@@ -0,0 +1,144 @@
+extern crate image;
+
+use gl;
+use image::RgbaImage;
+
+use super::texture::Texture;
+use super::vertex::Vertex;
+
+// The atlas has the characters from ' ' to '~' in rows of 16 cells
+const FIRST_CHAR: char = ' ';
+const LAST_CHAR: char = '~';
+const COLUMNS: usize = 16;
+// Width of the glyphs without any pixel, like the space
+const EMPTY_WIDTH: usize = 2;
+
+// How the lines of a text are placed with respect to the given position
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// A bitmap font made of white glyphs, so that any colour can be given to them
+pub struct Font {
+    texture: Texture,
+    texture_idx: i32,
+    cell: (usize, usize),
+    widths: Vec<usize>,
+}
+
+impl Font {
+    // Loads the font atlas `img`, named `name`, into the active texture unit, which is
+    // `texture_idx`. Each glyph is at the top left corner of its cell, of `cell` pixels,
+    // and its width goes up to the rightmost pixel that isn't transparent
+    pub fn from_image(
+        img: &RgbaImage,
+        name: &str,
+        cell: (usize, usize),
+        texture_idx: i32,
+    ) -> Result<Font, String> {
+        let count = LAST_CHAR as usize - FIRST_CHAR as usize + 1;
+        let rows = count.div_ceil(COLUMNS);
+        if (img.width() as usize) < cell.0 * COLUMNS || (img.height() as usize) < cell.1 * rows {
+            return Err(format!(
+                "the font {} must have {} rows of {} glyphs of {}x{} pixels",
+                name, rows, COLUMNS, cell.0, cell.1
+            ));
+        }
+        let widths = (0..count)
+            .map(|i| {
+                let (x0, y0) = ((i % COLUMNS) * cell.0, (i / COLUMNS) * cell.1);
+                (0..cell.0)
+                    .rev()
+                    .find(|&x| {
+                        (0..cell.1)
+                            .any(|y| img.get_pixel((x0 + x) as u32, (y0 + y) as u32).data[3] > 0)
+                    })
+                    .map_or(EMPTY_WIDTH, |x| x + 1)
+            })
+            .collect();
+        Ok(Font {
+            texture: Texture::from_image(img),
+            texture_idx,
+            cell,
+            widths,
+        })
+    }
+
+    // Returns the height of a line in font pixels
+    pub fn line_height(&self) -> usize {
+        self.cell.1
+    }
+
+    // Returns the width of `line` in font pixels
+    pub fn width(&self, line: &str) -> usize {
+        let advances: usize = line.chars().map(|c| self.widths[self.index(c)] + 1).sum();
+        advances.saturating_sub(1)
+    }
+
+    // Returns the number of quads needed to write `text`
+    pub fn quads(text: &str) -> usize {
+        text.chars().filter(|&c| c != '\n').count()
+    }
+
+    // Writes `text` as quads of six vertices each, one for every character but the new lines.
+    // The first line has its top at `y` and starts, is centered or ends at `x` depending on `align`,
+    // a pixel of the font is `scale` wide
+    pub fn layout(&self, text: &str, x: f32, y: f32, scale: f32, align: Align) -> Vec<Vertex> {
+        let px = self.texture.pixel_size();
+        let mut vertices = Vec::with_capacity(Font::quads(text) * 6);
+        for (row, line) in text.split('\n').enumerate() {
+            let width = self.width(line) as f32 * scale;
+            let mut left = match align {
+                Align::Left => x,
+                Align::Center => x - width / 2.0,
+                Align::Right => x - width,
+            };
+            let bottom = y - ((row + 1) * self.cell.1) as f32 * scale;
+            for c in line.chars() {
+                let i = self.index(c);
+                let (u, v) = (
+                    ((i % COLUMNS) * self.cell.0) as f32 * px.0,
+                    ((i / COLUMNS) * self.cell.1) as f32 * px.1,
+                );
+                let (w, h) = (self.cell.0 as f32, self.cell.1 as f32);
+                let corners = [
+                    ([left, bottom], [u, v + h * px.1]),
+                    ([left, bottom + h * scale], [u, v]),
+                    ([left + w * scale, bottom + h * scale], [u + w * px.0, v]),
+                    ([left + w * scale, bottom + h * scale], [u + w * px.0, v]),
+                    ([left + w * scale, bottom], [u + w * px.0, v + h * px.1]),
+                    ([left, bottom], [u, v + h * px.1]),
+                ];
+                for &(coord, texture_coord) in corners.iter() {
+                    vertices.push(Vertex {
+                        coord,
+                        texture_coord,
+                        texture_idx: self.texture_idx,
+                    });
+                }
+                left += (self.widths[i] + 1) as f32 * scale;
+            }
+        }
+        vertices
+    }
+
+    // Returns the index of the glyph of `c`, the characters missing from the atlas become '?'
+    fn index(&self, c: char) -> usize {
+        let c = if (FIRST_CHAR..=LAST_CHAR).contains(&c) {
+            c
+        } else {
+            '?'
+        };
+        c as usize - FIRST_CHAR as usize
+    }
+}
+
+// Sets the colour that multiplies everything drawn from now on, white leaves the textures as they are
+pub fn set_color(tint_loc: u32, color: (f32, f32, f32, f32)) {
+    unsafe {
+        gl::Uniform4f(tint_loc as i32, color.0, color.1, color.2, color.3);
+    }
+}
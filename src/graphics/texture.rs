@@ -1,11 +1,11 @@
 extern crate image;
 
 use std::ffi::c_void;
-use std::path::Path;
 use std::ptr;
 
 use gl;
 use gl::types as gl_t;
+use image::RgbaImage;
 
 pub struct Texture {
     id: gl_t::GLuint,
@@ -48,12 +48,15 @@ impl Texture {
         }
     }
 
-    pub fn from_file(path: &Path) -> Result<Texture, String> {
+    // Decodes the image `bytes`, whose name is used in the errors
+    pub fn decode(bytes: &[u8], name: &str) -> Result<RgbaImage, String> {
         // Open the image buffer
-        let img = image::open(path)
-            .or_else(|e| Err(format!("{}", e)))?
-            .to_rgba();
+        Ok(image::load_from_memory(bytes)
+            .map_err(|e| format!("can't load the texture {}: {}", name, e))?
+            .to_rgba())
+    }
 
+    pub fn from_image(img: &RgbaImage) -> Texture {
         let mut id: gl_t::GLuint = 0;
         unsafe {
             // Genereate a new texture
@@ -78,12 +81,12 @@ impl Texture {
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
             gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
         }
-        Ok(Texture {
+        Texture {
             id,
             width: img.width() as usize,
             height: img.height() as usize,
             pixel_size: (1.0 / img.width() as f32, 1.0 / img.height() as f32),
-        })
+        }
     }
 
     pub fn bind(&self) {
@@ -116,3 +119,11 @@ impl Texture {
         }
     }
 }
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
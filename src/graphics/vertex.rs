@@ -0,0 +1,8 @@
+// A corner of the triangles written in the vertex buffers:
+// its position, the point of the texture it shows and the texture unit it comes from
+#[derive(Clone, Default)]
+pub struct Vertex {
+    pub coord: [f32; 2],
+    pub texture_coord: [f32; 2],
+    pub texture_idx: i32,
+}
@@ -0,0 +1,428 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use sdl2::GameControllerSubsystem;
+use serde::{Deserialize, Serialize};
+
+// What the player can do, whatever key or button is bound to it
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    Dig,
+    Flag,
+    Chord,
+    // Move the field while the button is held, or scroll it with the wheel
+    Pan,
+    // The wheel zooms in when scrolled up if bound to zoom in, down if bound to zoom out
+    ZoomIn,
+    ZoomOut,
+    // Move the selected tile and the buttons of the menu
+    Up,
+    Down,
+    Left,
+    Right,
+    Restart,
+    Pause,
+    Menu,
+}
+
+// Values of the sticks closer to the centre than this are ignored
+const DEAD_ZONE: i16 = 8000;
+// How long a stick has to be held before it starts repeating its direction, and how often
+const FIRST_REPEAT: Duration = Duration::from_millis(300);
+const REPEAT: Duration = Duration::from_millis(120);
+// How long a finger has to stay on a tile to flag it
+pub const LONG_PRESS: Duration = Duration::from_millis(500);
+// The mouse SDL makes up from the touches has this id, the fingers are handled on their own
+pub const TOUCH_MOUSE_ID: u32 = u32::MAX;
+
+// A key, a mouse button, the mouse wheel or a button of a game controller
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Input {
+    Key(Keycode),
+    Mouse(MouseButton),
+    Wheel,
+    Pad(Button),
+}
+
+impl FromStr for Input {
+    type Err = String;
+
+    // Keys are named like SDL does, e.g. "Space", "Keypad +" or "W", regardless of the case,
+    // and so are the buttons of the controllers after "pad", e.g. "pad a" or "pad dpup"
+    fn from_str(s: &str) -> Result<Input, String> {
+        let name = s.trim().to_lowercase();
+        if let Some(button) = name.strip_prefix("pad ") {
+            return Button::from_string(button.trim())
+                .map(Input::Pad)
+                .ok_or_else(|| format!("unknown controller button '{}'", s));
+        }
+        match name.as_str() {
+            "mouse left" => Ok(Input::Mouse(MouseButton::Left)),
+            "mouse middle" => Ok(Input::Mouse(MouseButton::Middle)),
+            "mouse right" => Ok(Input::Mouse(MouseButton::Right)),
+            "mouse x1" => Ok(Input::Mouse(MouseButton::X1)),
+            "mouse x2" => Ok(Input::Mouse(MouseButton::X2)),
+            "wheel" => Ok(Input::Wheel),
+            _ => Keycode::from_name(s.trim())
+                .map(Input::Key)
+                .ok_or_else(|| format!("unknown key or button '{}'", s)),
+        }
+    }
+}
+
+// Which inputs do each command, and how the presses of the mouse become drags
+pub struct Bindings {
+    bound: Vec<(Input, Command)>,
+    // Pixels the mouse has to move while pressed to start dragging
+    pub drag_threshold: i32,
+    // How long a button can be held to still be a click rather than a drag
+    pub click_threshold: Duration,
+}
+
+// The bindings file as it's written, with the inputs of each command by name
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct BindingsFile {
+    drag_threshold: i32,
+    // Milliseconds
+    click_threshold: u64,
+    commands: BTreeMap<Command, Vec<String>>,
+}
+
+impl Default for BindingsFile {
+    fn default() -> BindingsFile {
+        let commands = [
+            (Command::Dig, &["mouse left", "Space", "pad a"][..]),
+            (Command::Flag, &["mouse right", "F", "pad x"]),
+            (Command::Chord, &["mouse middle", "C", "pad b"]),
+            (Command::Pan, &["mouse left"]),
+            (Command::ZoomIn, &["wheel", "=", "+", "Keypad +"]),
+            (Command::ZoomOut, &["-", "Keypad -"]),
+            (Command::Up, &["Up", "W", "pad dpup"]),
+            (Command::Down, &["Down", "S", "pad dpdown"]),
+            (Command::Left, &["Left", "A", "pad dpleft"]),
+            (Command::Right, &["Right", "D", "pad dpright"]),
+            (Command::Restart, &["R", "pad y"]),
+            (Command::Pause, &["P", "pad start"]),
+            (Command::Menu, &["Escape", "pad back"]),
+        ];
+        BindingsFile {
+            drag_threshold: 20,
+            click_threshold: 100,
+            commands: commands
+                .iter()
+                .map(|&(command, inputs)| (command, inputs.iter().map(|s| s.to_string()).collect()))
+                .collect(),
+        }
+    }
+}
+
+impl Bindings {
+    // Reads the bindings in the TOML file at `path`, the commands missing from it keep their
+    // default inputs. When the file doesn't exist it gets created with the defaults,
+    // so that there is something to edit
+    pub fn load(path: &Path) -> Result<Bindings, String> {
+        let file = match fs::read_to_string(path) {
+            Ok(s) => {
+                let mut file: BindingsFile = toml::from_str(&s)
+                    .map_err(|e| format!("invalid bindings in {}: {}", path.display(), e))?;
+                for (command, inputs) in BindingsFile::default().commands {
+                    file.commands.entry(command).or_insert(inputs);
+                }
+                file
+            }
+            Err(_) => {
+                let file = BindingsFile::default();
+                if let Ok(s) = toml::to_string(&file) {
+                    let _ = path.parent().map(fs::create_dir_all);
+                    let _ = fs::write(path, s);
+                }
+                file
+            }
+        };
+        Bindings::from_file(file)
+            .map_err(|e| format!("invalid bindings in {}: {}", path.display(), e))
+    }
+
+    // Each input can do a single command, apart from panning that only starts once the mouse is
+    // dragged: it can share a button with a command done by clicking
+    fn from_file(file: BindingsFile) -> Result<Bindings, String> {
+        let mut bound: Vec<(Input, Command)> = Vec::new();
+        for (command, inputs) in file.commands {
+            for name in inputs {
+                let input = name.parse()?;
+                let taken = bound.iter().find(|&&(other_input, other)| {
+                    other_input == input && (other == Command::Pan) == (command == Command::Pan)
+                });
+                if let Some(&(_, other)) = taken {
+                    return Err(format!(
+                        "'{}' is bound to both {:?} and {:?}",
+                        name, other, command
+                    ));
+                }
+                bound.push((input, command));
+            }
+        }
+        Ok(Bindings {
+            bound,
+            drag_threshold: file.drag_threshold,
+            click_threshold: Duration::from_millis(file.click_threshold),
+        })
+    }
+
+    // Tells whether or not `input` does `command`
+    pub fn is(&self, input: Input, command: Command) -> bool {
+        self.bound.contains(&(input, command))
+    }
+
+    // Tells whether or not `event` is a key or a button of a controller
+    // that does `command` getting pressed
+    pub fn pressed(&self, event: &Event, command: Command) -> bool {
+        match *event {
+            Event::KeyDown {
+                keycode: Some(key), ..
+            } => self.is(Input::Key(key), command),
+            Event::ControllerButtonDown { button, .. } => self.is(Input::Pad(button), command),
+            _ => false,
+        }
+    }
+
+    // Tells whether or not `event` presses the buttons of the menu: it does when it's
+    // the release of a mouse button that digs, enter or a key or a button that digs getting pressed
+    pub fn confirms(&self, event: &Event) -> bool {
+        match *event {
+            Event::MouseButtonUp { mouse_btn, .. } => {
+                self.is(Input::Mouse(mouse_btn), Command::Dig)
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Return),
+                ..
+            } => true,
+            _ => self.pressed(event, Command::Dig),
+        }
+    }
+
+    // Returns the direction the key or the button pressed by `event` moves to, `y` going up
+    pub fn direction(&self, event: &Event) -> Option<(i32, i32)> {
+        let directions = [
+            (Command::Up, (0, 1)),
+            (Command::Down, (0, -1)),
+            (Command::Left, (-1, 0)),
+            (Command::Right, (1, 0)),
+        ];
+        directions
+            .iter()
+            .find(|&&(command, _)| self.pressed(event, command))
+            .map(|&(_, direction)| direction)
+    }
+}
+
+// The game controllers plugged in, opened as they get connected
+pub struct Gamepads {
+    subsystem: GameControllerSubsystem,
+    controllers: Vec<GameController>,
+}
+
+impl Gamepads {
+    // SDL tells about the controllers already connected with the same events as the new ones
+    pub fn new(subsystem: GameControllerSubsystem) -> Gamepads {
+        Gamepads {
+            subsystem,
+            controllers: Vec::new(),
+        }
+    }
+
+    // Opens the controllers that get connected and closes the ones that get disconnected
+    pub fn plug(&mut self, event: &Event) {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => {
+                if let Ok(controller) = self.subsystem.open(which) {
+                    self.controllers.push(controller);
+                }
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.controllers.retain(|c| c.instance_id() != which);
+            }
+            _ => {}
+        }
+    }
+
+    // Returns where the stick made of the axes `x` and `y` is tilted, from -1 to 1 with `y` going
+    // up, on the first controller that has it out of the dead zone
+    pub fn stick(&self, x: Axis, y: Axis) -> (f32, f32) {
+        self.controllers
+            .iter()
+            .find_map(|c| tilt(c.axis(x), c.axis(y)))
+            .unwrap_or((0.0, 0.0))
+    }
+
+    // Returns how much the trigger `axis` is pressed, from 0 to 1, on the controller pressing it most
+    pub fn trigger(&self, axis: Axis) -> f32 {
+        let value = self.controllers.iter().map(|c| c.axis(axis)).max();
+        match value {
+            Some(value) if value > DEAD_ZONE => value as f32 / i16::MAX as f32,
+            _ => 0.0,
+        }
+    }
+}
+
+// Returns where a stick with the axes at `x` and `y` is tilted, from -1 to 1 with `y` going up,
+// unless it's in the dead zone
+fn tilt(x: i16, y: i16) -> Option<(f32, f32)> {
+    if x.saturating_abs() <= DEAD_ZONE && y.saturating_abs() <= DEAD_ZONE {
+        return None;
+    }
+    Some((x as f32 / i16::MAX as f32, -(y as f32) / i16::MAX as f32))
+}
+
+// Turns a stick held in a direction into steps, like a key getting repeated while it's held
+#[derive(Default)]
+pub struct StickRepeat {
+    held: Option<((i32, i32), Instant)>,
+}
+
+impl StickRepeat {
+    // Returns the direction to step towards, if it's time to at `now`, with the stick tilted to `stick`
+    pub fn update(&mut self, stick: (f32, f32), now: Instant) -> Option<(i32, i32)> {
+        let direction = match stick {
+            (x, y) if x == 0.0 && y == 0.0 => None,
+            (x, y) if x.abs() > y.abs() => Some((x.signum() as i32, 0)),
+            (_, y) => Some((0, y.signum() as i32)),
+        };
+        match (direction, self.held) {
+            (None, _) => {
+                self.held = None;
+                None
+            }
+            (Some(direction), Some((held, next))) if direction == held => {
+                if now < next {
+                    return None;
+                }
+                self.held = Some((direction, next + REPEAT));
+                Some(direction)
+            }
+            (Some(direction), _) => {
+                self.held = Some((direction, now + FIRST_REPEAT));
+                Some(direction)
+            }
+        }
+    }
+}
+
+// The first finger on the screen, that taps or long presses unless it moves or gets joined by others
+pub struct Touch {
+    pub finger: i64,
+    // Where it went down, in pixels of the window
+    pub start: (i32, i32),
+    since: Instant,
+    // It has already flagged, moved or pinched: lifting it does nothing
+    pub done: bool,
+}
+
+impl Touch {
+    pub fn new(finger: i64, start: (i32, i32)) -> Touch {
+        Touch {
+            finger,
+            start,
+            since: Instant::now(),
+            done: false,
+        }
+    }
+
+    // Returns how far the long press has gone, from 0 to 1 when it flags
+    pub fn hold(&self) -> f32 {
+        (self.since.elapsed().as_secs_f32() / LONG_PRESS.as_secs_f32()).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(toml: &str) -> BindingsFile {
+        let mut file: BindingsFile = toml::from_str(toml).unwrap();
+        for (command, inputs) in BindingsFile::default().commands {
+            file.commands.entry(command).or_insert(inputs);
+        }
+        file
+    }
+
+    #[test]
+    fn default_bindings_are_valid() {
+        let bindings = Bindings::from_file(BindingsFile::default()).unwrap();
+        let space = Input::Key(Keycode::Space);
+        assert!(bindings.is(space, Command::Dig));
+        assert!(!bindings.is(space, Command::Pause));
+        // The left button digs when clicked and pans when dragged
+        let left = Input::Mouse(MouseButton::Left);
+        assert!(bindings.is(left, Command::Dig) && bindings.is(left, Command::Pan));
+    }
+
+    #[test]
+    fn inputs_bound_twice_are_refused() {
+        let twice = file("[commands]\npause = [\"Space\"]\n");
+        assert_eq!(
+            Bindings::from_file(twice).err(),
+            Some("'Space' is bound to both Dig and Pause".to_string())
+        );
+        let same_command = file("[commands]\nflag = [\"F\", \"F\"]\n");
+        assert_eq!(
+            Bindings::from_file(same_command).err(),
+            Some("'F' is bound to both Flag and Flag".to_string())
+        );
+        let pan = file("[commands]\npan = [\"mouse right\"]\n");
+        assert!(Bindings::from_file(pan).is_ok());
+    }
+
+    #[test]
+    fn sticks_in_the_dead_zone_are_ignored() {
+        assert_eq!(tilt(0, 0), None);
+        assert_eq!(tilt(DEAD_ZONE, -DEAD_ZONE), None);
+        assert_eq!(
+            tilt(i16::MIN, 0),
+            Some((i16::MIN as f32 / i16::MAX as f32, 0.0))
+        );
+        // Up is negative for SDL
+        assert_eq!(tilt(0, -i16::MAX), Some((0.0, 1.0)));
+        assert_eq!(tilt(DEAD_ZONE + 1, 0).map(|(x, _)| x > 0.0), Some(true));
+    }
+
+    #[test]
+    fn stick_repeats_its_direction_while_held() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut repeat = StickRepeat::default();
+        assert_eq!(repeat.update((0.0, 0.0), at(0)), None);
+        // The first step is right away, the next ones after the first repeat and then every repeat
+        assert_eq!(repeat.update((0.8, 0.3), at(0)), Some((1, 0)));
+        assert_eq!(repeat.update((0.8, 0.3), at(299)), None);
+        assert_eq!(repeat.update((0.8, 0.3), at(300)), Some((1, 0)));
+        assert_eq!(repeat.update((0.8, 0.3), at(419)), None);
+        assert_eq!(repeat.update((0.8, 0.3), at(420)), Some((1, 0)));
+        assert_eq!(repeat.update((0.8, 0.3), at(540)), Some((1, 0)));
+        // Changing direction steps right away and waits the first repeat again
+        assert_eq!(repeat.update((0.1, -0.5), at(541)), Some((0, -1)));
+        assert_eq!(repeat.update((0.1, -0.5), at(800)), None);
+        // Releasing the stick stops the repeats
+        assert_eq!(repeat.update((0.0, 0.0), at(900)), None);
+        assert_eq!(repeat.update((0.0, 0.0), at(2000)), None);
+        assert_eq!(repeat.update((0.0, 0.9), at(2001)), Some((0, 1)));
+    }
+
+    #[test]
+    fn unknown_inputs_are_refused() {
+        let unknown = file("[commands]\nflag = [\"pad z\"]\n");
+        assert_eq!(
+            Bindings::from_file(unknown).err(),
+            Some("unknown controller button 'pad z'".to_string())
+        );
+    }
+}
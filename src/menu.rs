@@ -0,0 +1,647 @@
+use std::time::{Duration, Instant};
+
+use minesweeper::difficulty::{MAX_SIZE, MIN_SIZE};
+use minesweeper::{BoardId, Difficulty, GenerationMode, Records, SafeArea, SavedGame};
+use sdl2::controller::Axis;
+use sdl2::event::Event;
+
+use crate::game::GameScene;
+use crate::graphics::mesh::Mesh;
+use crate::graphics::text::{self, Align, Font};
+use crate::graphics::vbo::VertexBufferObject as VBO;
+use crate::graphics::vertex::Vertex;
+use crate::input::StickRepeat;
+use crate::scene::{self, Animation, Context, Scene, Transition};
+use crate::theme::Theme;
+use crate::{
+    apply_sprite, data_dir, mix, move_rect, next_theme, put_number, put_rect, reset_rect,
+    rotate_tecture_rect, INK, PX, RECORDS_FILE, SAVE_FILE, WHITE,
+};
+
+// Number of vertices of the menu
+const MENU_SIZE: usize = 20 * 6;
+
+// Top left corner of the buttons of the menu, the index is the value of 'selected' - 1:
+// the sizes, accept, no guess, custom size, the arrows to edit the custom size, continue,
+// statistics and theme
+const BUTTONS: [(f32, f32); 15] = [
+    (-30.0, -30.0),
+    (-8.0, -30.0),
+    (22.0, -30.0),
+    (1.0, -53.0),
+    (46.0, 62.0),
+    (-6.0, 62.0),
+    (-2.0, 1.0),
+    (24.0, 1.0),
+    (-2.0, -10.0),
+    (24.0, -10.0),
+    (-2.0, -21.0),
+    (24.0, -21.0),
+    (20.0, -41.0),
+    (39.0, 31.0),
+    (39.0, 12.0),
+];
+
+enum Phase {
+    // The contract comes down, then waits for the choices and goes back up
+    SlideIn(Animation),
+    Choose,
+    SlideOut(Animation),
+}
+
+// The contract where the size of the field and the generation mode are chosen
+pub struct MenuScene {
+    mesh: Mesh,
+    data: Vec<Vertex>,
+    phase: Phase,
+    // Game that can be continued
+    saved: Option<SavedGame>,
+    selected: usize,
+    size: usize,
+    mode: GenerationMode,
+    theme_name: String,
+    // Vertical position of the mark on the selected size
+    size_mark: f32,
+    // Vertical position of the mark on the accept or continue box
+    accept_mark: f32,
+    // Whether or not the saved game has been chosen
+    resume: bool,
+    left_stick: StickRepeat,
+    // Where the keys or the stick move the hover mark this frame
+    step: Option<(i32, i32)>,
+}
+
+impl MenuScene {
+    pub fn new(ctx: &mut Context) -> Result<MenuScene, String> {
+        // Look for a game that can be continued
+        let saved = SavedGame::load(&data_dir().join(SAVE_FILE))
+            .ok()
+            .filter(|game| !game.field.is_over());
+
+        let mut data = vec![Default::default(); MENU_SIZE];
+        texture_menu(&mut data, &ctx.theme);
+        // The no guess box keeps the last choice
+        if ctx.settings.mode() == GenerationMode::NoGuess {
+            put_rect(
+                &mut data,
+                24,
+                PX * 44.999,
+                PX * 53.999,
+                PX * 8.99,
+                PX * 8.99,
+            );
+        } else {
+            reset_rect(&mut data, 24);
+        }
+        hide_custom_panel(&mut data);
+        reset_rect(&mut data, 108);
+        reset_rect(&mut data, 114);
+        put_rect(&mut data, 0, -1.0, -1.0, 2.0, 2.0);
+        put_rect(&mut data, 6, -0.5, -1.0, 0.75, 0.25);
+
+        let mesh = Mesh::new(&ctx.program, MENU_SIZE)?;
+        VBO::write(0, &data);
+        let animations = ctx.settings.game.animations;
+        Ok(MenuScene {
+            mesh,
+            data,
+            phase: Phase::SlideIn(Animation::new(animations, Duration::from_secs(1))),
+            saved,
+            selected: 0,
+            size: 0,
+            mode: ctx.settings.mode(),
+            theme_name: ctx.settings.game.theme.clone(),
+            size_mark: 0.0,
+            accept_mark: PX * -61.001,
+            resume: false,
+            left_stick: StickRepeat::default(),
+            step: None,
+        })
+    }
+
+    // The arrows can be used only when the custom size is selected
+    // and continue only when there is a saved game
+    fn enabled(&self, n: usize) -> bool {
+        match n {
+            7..=12 => self.size == 4,
+            13 => self.saved.is_some(),
+            _ => true,
+        }
+    }
+
+    // Does what the hovered button is for
+    fn confirm(&mut self, ctx: &mut Context) -> Result<Transition, String> {
+        match self.selected {
+            0 => {}
+            n @ 1..=3 | n @ 6 => {
+                let (x, y) = BUTTONS[n - 1];
+                self.size_mark = PX * (y - 8.001);
+                if n == 6 {
+                    self.size = 4;
+                    put_rect(
+                        &mut self.data,
+                        18,
+                        PX * (x - 1.001),
+                        self.size_mark,
+                        PX * 8.99,
+                        PX * 8.99,
+                    );
+                    put_custom_panel(&mut self.data, ctx.custom, 0.0, &ctx.theme);
+                } else {
+                    self.size = n;
+                    put_rect(
+                        &mut self.data,
+                        18,
+                        PX * (x - 0.001),
+                        self.size_mark,
+                        PX * 8.99,
+                        PX * 8.99,
+                    );
+                    hide_custom_panel(&mut self.data);
+                }
+                VBO::write(18, &self.data[18..]);
+            }
+            4 => {
+                if self.size > 0 {
+                    move_rect(&mut self.data, 12, Some((PX, PX * 8.99)), None);
+                    VBO::write(12, &self.data[12..18]);
+                    return self.leave(ctx);
+                }
+            }
+            5 => {
+                if self.mode == GenerationMode::Classic {
+                    self.mode = GenerationMode::NoGuess;
+                    put_rect(
+                        &mut self.data,
+                        24,
+                        PX * 44.999,
+                        PX * 53.999,
+                        PX * 8.99,
+                        PX * 8.99,
+                    );
+                } else {
+                    self.mode = GenerationMode::Classic;
+                    reset_rect(&mut self.data, 24);
+                }
+                VBO::write(24, &self.data[24..30]);
+            }
+            n @ 7..=12 => {
+                let delta = if n % 2 == 1 { -1 } else { 1 };
+                change_custom(&mut ctx.custom, (n - 7) / 2, delta);
+                put_custom_panel(&mut self.data, ctx.custom, 0.0, &ctx.theme);
+                VBO::write(30, &self.data[30..]);
+            }
+            13 => {
+                // The hover is already on the box, it will become the mark
+                self.accept_mark = PX * -49.001;
+                self.resume = true;
+                return self.leave(ctx);
+            }
+            14 => {
+                // The sheet hides the contract, and with it the hovered button
+                self.selected = 0;
+                reset_rect(&mut self.data, 12);
+                VBO::write(12, &self.data[12..18]);
+                let stats = StatsScene::new(ctx, self.mode)?;
+                return Ok(Transition::Push(Box::new(stats)));
+            }
+            15 => {
+                // Switch to the next theme, the current one stays when no other can be loaded
+                let (name, loaded) = match next_theme(&self.theme_name, &ctx.settings) {
+                    Ok(next) => next,
+                    Err(_) => return Ok(Transition::Stay),
+                };
+                ctx.theme = loaded;
+                self.theme_name = name;
+                texture_menu(&mut self.data, &ctx.theme);
+                if self.size == 4 {
+                    put_custom_panel(&mut self.data, ctx.custom, 0.0, &ctx.theme);
+                }
+                VBO::write(0, &self.data);
+            }
+            _ => unreachable!(),
+        }
+        ctx.redraw = true;
+        Ok(Transition::Stay)
+    }
+
+    // Keeps the choices and sends the contract back up
+    fn leave(&mut self, ctx: &mut Context) -> Result<Transition, String> {
+        // Remember the choices made in the menu for the next time
+        let settings = &mut ctx.settings;
+        if self.mode != settings.mode()
+            || ctx.custom != settings.custom()
+            || self.theme_name != settings.game.theme
+        {
+            settings.save_menu(&ctx.settings_path, self.mode, ctx.custom, &self.theme_name)?;
+        }
+        apply_sprite(&mut self.data, 12, &ctx.theme.ui, ctx.theme.atlas.ui.check);
+        let animations = ctx.settings.game.animations;
+        self.phase = Phase::SlideOut(Animation::new(animations, Duration::from_millis(500)));
+        ctx.redraw = true;
+        Ok(Transition::Stay)
+    }
+
+    // Creates the mine field, of the size of the saved game, replay or imported board
+    // if there is one
+    fn start_game(&mut self, ctx: &mut Context) -> Result<GameScene, String> {
+        let resumed = self.saved.take().filter(|_| self.resume);
+        let given = resumed
+            .as_ref()
+            .map(|game| game.id)
+            .or(ctx.playback.as_ref().map(|replay| replay.id))
+            .or(ctx.imported.as_ref().map(|field| {
+                BoardId::with_seed(0, field.width(), field.height(), field.bombs(), (0, 0))
+            }));
+        let mut mode = self.mode;
+        let custom = ctx.custom;
+        let difficulty = match (given, ctx.restart.take(), self.size) {
+            (Some(id), _, _) => Difficulty {
+                width: id.width,
+                height: id.height,
+                bombs: id.bombs,
+            },
+            (None, Some((last, last_mode)), _) => {
+                mode = last_mode;
+                last
+            }
+            (None, None, 1) => Difficulty::BEGINNER,
+            (None, None, 2) => Difficulty::INTERMEDIATE,
+            (None, None, 3) => Difficulty::EXPERT,
+            (None, None, 4) => Difficulty::custom(custom.width, custom.height, custom.bombs)?,
+            _ => unreachable!(),
+        };
+        GameScene::new(ctx, difficulty, mode, resumed)
+    }
+}
+
+impl Scene for MenuScene {
+    fn handle_event(&mut self, ctx: &mut Context, event: &Event) -> Result<Transition, String> {
+        // The contract can't be used while it moves
+        if let Phase::SlideIn(_) | Phase::SlideOut(_) = self.phase {
+            return Ok(Transition::Stay);
+        }
+        self.mesh.bind();
+        match *event {
+            // When a button that digs gets released, or enter or a key or a button
+            // of a controller that digs gets pressed, on one of the buttons
+            _ if ctx.bindings.confirms(event) => return self.confirm(ctx),
+            // The arrows, WASD and the d-pad move to the closest button in their direction
+            Event::KeyDown { .. } | Event::ControllerButtonDown { .. } => {
+                self.step = ctx.bindings.direction(event);
+            }
+            // When scrolling over the arrows change the custom size
+            Event::MouseWheel { y, .. } if (7..=12).contains(&self.selected) => {
+                change_custom(&mut ctx.custom, (self.selected - 7) / 2, y);
+                put_custom_panel(&mut self.data, ctx.custom, 0.0, &ctx.theme);
+                VBO::write(30, &self.data[30..]);
+                ctx.redraw = true;
+            }
+            // When the mouse is moved
+            Event::MouseMotion { x, y, .. } => {
+                let xx = x as f32 * ctx.window_px_size.0 - 1.0 / ctx.aspect.0;
+                let yy = 1.0 / ctx.aspect.1 - y as f32 * ctx.window_px_size.1;
+
+                let hovered = BUTTONS
+                    .iter()
+                    .enumerate()
+                    .position(|(i, &(x, y))| {
+                        self.enabled(i + 1)
+                            && xx > PX * x
+                            && xx < PX * (x + 7.0)
+                            && yy < PX * y
+                            && yy > PX * (y - 7.0)
+                    })
+                    .map_or(0, |i| i + 1);
+
+                if hovered != self.selected {
+                    self.selected = hovered;
+                    hover_button(&mut self.data, &BUTTONS, self.selected);
+                    ctx.redraw = true;
+                }
+            }
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn update(&mut self, ctx: &mut Context, dt: Duration) -> Result<Transition, String> {
+        self.mesh.bind();
+        match &mut self.phase {
+            Phase::SlideIn(slide) => {
+                let elapsed = slide.advance(dt);
+                let over = slide.is_over();
+                move_rect(
+                    &mut self.data,
+                    0,
+                    None,
+                    Some((mix(ctx.aspect.1, -1.0, elapsed), 2.0)),
+                );
+                move_rect(
+                    &mut self.data,
+                    6,
+                    None,
+                    Some((mix(ctx.aspect.1, -1.0, elapsed), 0.25)),
+                );
+                VBO::write(0, &self.data[..12]);
+                ctx.redraw = true;
+                if over {
+                    self.phase = Phase::Choose;
+                    // Offer to continue the saved game next to the status
+                    if self.saved.is_some() {
+                        put_rect(
+                            &mut self.data,
+                            114,
+                            PX * -16.0,
+                            PX * -48.0,
+                            PX * 43.0,
+                            PX * 7.0,
+                        );
+                        VBO::write(114, &self.data[114..]);
+                    }
+                    // A replay, an imported board or a restarted game don't need anything
+                    // from the menu
+                    if ctx.playback.is_some() || ctx.imported.is_some() || ctx.restart.is_some() {
+                        return self.leave(ctx);
+                    }
+                }
+            }
+            Phase::Choose => {
+                // The left stick moves between the buttons too
+                let stick = ctx.gamepads.stick(Axis::LeftX, Axis::LeftY);
+                let step = self
+                    .step
+                    .take()
+                    .or(self.left_stick.update(stick, Instant::now()));
+                if let Some(direction) = step {
+                    let hovered =
+                        next_button(&BUTTONS, self.selected, direction, |n| self.enabled(n));
+                    if hovered != self.selected {
+                        self.selected = hovered;
+                        hover_button(&mut self.data, &BUTTONS, self.selected);
+                        ctx.redraw = true;
+                    }
+                }
+            }
+            Phase::SlideOut(slide) => {
+                let elapsed = slide.advance(dt);
+                let over = slide.is_over();
+                let data = &mut self.data;
+                move_rect(data, 0, None, Some((mix(-ctx.aspect.1, 1.0, elapsed), 2.0)));
+                move_rect(
+                    data,
+                    6,
+                    None,
+                    Some((mix(-ctx.aspect.1, 1.0, elapsed), 0.25)),
+                );
+                move_rect(
+                    data,
+                    12,
+                    None,
+                    Some((self.accept_mark + 2.0 * elapsed, PX * 8.99)),
+                );
+                move_rect(
+                    data,
+                    18,
+                    None,
+                    Some((self.size_mark + 2.0 * elapsed, PX * 8.99)),
+                );
+                move_rect(
+                    data,
+                    24,
+                    None,
+                    Some((PX * 53.999 + 2.0 * elapsed, PX * 8.99)),
+                );
+                if self.size == 4 {
+                    put_custom_panel(data, ctx.custom, 2.0 * elapsed, &ctx.theme);
+                }
+                if self.saved.is_some() {
+                    move_rect(
+                        data,
+                        114,
+                        None,
+                        Some((PX * -48.0 + 2.0 * elapsed, PX * 7.0)),
+                    );
+                }
+                VBO::write(0, data);
+                ctx.redraw = true;
+                if over {
+                    let game = self.start_game(ctx)?;
+                    return Ok(Transition::Replace(Box::new(game)));
+                }
+            }
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        self.mesh.bind();
+        ctx.view((0.0, 0.0), 1.0);
+        scene::draw(0, MENU_SIZE);
+    }
+}
+
+// The statistics of the games played, written on a sheet on top of the contract
+pub struct StatsScene {
+    mesh: Mesh,
+    len: usize,
+}
+
+impl StatsScene {
+    pub fn new(ctx: &Context, mode: GenerationMode) -> Result<StatsScene, String> {
+        // A corrupted file shows no statistics rather than stopping the game
+        let records = Records::load(&data_dir().join(RECORDS_FILE)).unwrap_or_default();
+        // Blank sheet where the statistics are written
+        let mut data = vec![Default::default(); 6];
+        put_rect(&mut data, 0, PX * -49.0, PX * -50.0, PX * 98.0, PX * 102.0);
+        apply_sprite(&mut data, 0, &ctx.theme.ui, ctx.theme.atlas.ui.paper);
+        data.extend(put_stats(&ctx.theme.font, &records, ctx.custom, mode));
+
+        let mesh = Mesh::new(&ctx.program, data.len())?;
+        VBO::write(0, &data);
+        Ok(StatsScene {
+            mesh,
+            len: data.len(),
+        })
+    }
+}
+
+impl Scene for StatsScene {
+    fn handle_event(&mut self, _ctx: &mut Context, event: &Event) -> Result<Transition, String> {
+        // Any click, key or button closes the statistics
+        Ok(match event {
+            Event::MouseButtonUp { .. }
+            | Event::KeyDown { .. }
+            | Event::ControllerButtonDown { .. } => Transition::Pop,
+            _ => Transition::Stay,
+        })
+    }
+
+    fn update(&mut self, _ctx: &mut Context, _dt: Duration) -> Result<Transition, String> {
+        Ok(Transition::Stay)
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        self.mesh.bind();
+        ctx.view((0.0, 0.0), 1.0);
+        scene::draw(0, 6);
+        text::set_color(ctx.tint_loc, INK);
+        scene::draw(6, self.len - 6);
+        text::set_color(ctx.tint_loc, WHITE);
+    }
+}
+
+// Changes by `delta` the width, height or bombs (`row` 0, 1 or 2) of the custom field,
+// keeping the values inside the allowed ranges
+fn change_custom(custom: &mut Difficulty, row: usize, delta: i32) {
+    let add = |value: usize| (value as i64 + delta as i64).max(0) as usize;
+    match row {
+        0 => custom.width = add(custom.width).clamp(MIN_SIZE, MAX_SIZE),
+        1 => custom.height = add(custom.height).clamp(MIN_SIZE, MAX_SIZE),
+        _ => custom.bombs = add(custom.bombs).max(1),
+    }
+    custom.bombs = custom
+        .bombs
+        .min(Difficulty::max_bombs(custom.width, custom.height));
+}
+
+// Puts the panel used to edit the custom field on the menu, `dy` moves it vertically
+fn put_custom_panel(vec: &mut Vec<Vertex>, custom: Difficulty, dy: f32, theme: &Theme) {
+    put_rect(vec, 30, PX * -48.0, PX * -29.0 + dy, PX * 98.0, PX * 43.0);
+    apply_sprite(vec, 30, &theme.ui, theme.atlas.ui.custom_panel);
+    let values = [custom.width, custom.height, custom.bombs];
+    for (i, &value) in values.iter().enumerate() {
+        let y = PX * (-6.0 - 11.0 * i as f32) + dy;
+        put_number(vec, 36 + i * 24, 4, value, PX * 7.0, y, theme);
+    }
+}
+
+// Gives the rects of the menu their sprites, the custom size panel has its own
+fn texture_menu(vec: &mut Vec<Vertex>, theme: &Theme) {
+    let (ui, atlas) = (&theme.ui, &theme.atlas.ui);
+    apply_sprite(vec, 0, ui, atlas.menu);
+    apply_sprite(vec, 6, ui, atlas.roll);
+    rotate_tecture_rect(vec, 6);
+    apply_sprite(vec, 12, ui, atlas.hover);
+    apply_sprite(vec, 18, ui, atlas.check);
+    apply_sprite(vec, 24, ui, atlas.check);
+    apply_sprite(vec, 108, ui, atlas.hover);
+    apply_sprite(vec, 114, ui, atlas.continue_label);
+}
+
+fn hide_custom_panel(vec: &mut Vec<Vertex>) {
+    for idx in (30..108).step_by(6) {
+        reset_rect(vec, idx);
+    }
+}
+
+// Writes the statistics of the games played with `mode` as a table on the sheet of the menu,
+// with a column for each size of the menu, the custom one being the last chosen.
+// The games of the menu always begin with an opening
+fn put_stats(
+    font: &Font,
+    records: &Records,
+    custom: Difficulty,
+    mode: GenerationMode,
+) -> Vec<Vertex> {
+    let scale = PX * 0.5;
+    let line = font.line_height() as f32 * scale;
+    let title = match mode {
+        GenerationMode::Classic => "STATISTICS\nclassic",
+        GenerationMode::NoGuess => "STATISTICS\nno guess",
+    };
+    let mut vertices = font.layout(title, 0.0, PX * 50.0, scale, Align::Center);
+
+    let top = PX * 50.0 - 3.0 * line;
+    let labels = "\nplayed\nwon\nwin rate\nbest time\navg time\navg 3BV/s\nstreak\nbest streak";
+    vertices.extend(font.layout(labels, PX * -47.0, top, scale, Align::Left));
+    let columns = [
+        ("9x9", Difficulty::BEGINNER),
+        ("16x16", Difficulty::INTERMEDIATE),
+        ("30x16", Difficulty::EXPERT),
+        ("custom", custom),
+    ];
+    let seconds = |time: Option<Duration>| {
+        time.map_or("-".to_string(), |t| format!("{:.2}", t.as_secs_f64()))
+    };
+    for (i, &(name, difficulty)) in columns.iter().enumerate() {
+        let summary = records.summary(difficulty, mode, SafeArea::default());
+        let column = if summary.played == 0 {
+            format!("{}\n-\n-\n-\n-\n-\n-\n-\n-", name)
+        } else {
+            format!(
+                "{}\n{}\n{}\n{:.0}%\n{}\n{}\n{:.2}\n{}\n{}",
+                name,
+                summary.played,
+                summary.won,
+                summary.win_rate() * 100.0,
+                seconds(summary.best_time),
+                seconds(summary.average_time),
+                summary.average_bbbv_per_second,
+                summary.current_streak,
+                summary.longest_streak
+            )
+        };
+        let right = PX * (-1.0 + 16.0 * i as f32);
+        vertices.extend(font.layout(&column, right, top, scale, Align::Right));
+    }
+
+    let footer = format!(
+        "custom: {}x{}, {} bombs",
+        custom.width, custom.height, custom.bombs
+    );
+    vertices.extend(font.layout(&footer, 0.0, top - 10.0 * line, scale, Align::Center));
+    vertices
+}
+
+// Puts the hover mark on the button `selected` of the menu, none when it's 0
+fn hover_button(menu_data: &mut Vec<Vertex>, buttons: &[(f32, f32)], selected: usize) {
+    reset_rect(menu_data, 12);
+    reset_rect(menu_data, 108);
+    if selected > 0 {
+        let (x, y) = buttons[selected - 1];
+        // The arrows are on top of the custom size panel
+        let idx = if (7..=12).contains(&selected) {
+            108
+        } else {
+            12
+        };
+        put_rect(
+            menu_data,
+            idx,
+            PX * (x - 1.001),
+            PX * (y - 8.001),
+            PX * 8.99,
+            PX * 8.99,
+        );
+    }
+    VBO::write(12, &menu_data[12..18]);
+    VBO::write(108, &menu_data[108..]);
+}
+
+// Returns the enabled button of the menu closest to `selected` going towards `direction`,
+// preferring the ones in line with it, or `selected` itself when there are none.
+// When no button is selected the first one is
+fn next_button(
+    buttons: &[(f32, f32)],
+    selected: usize,
+    direction: (i32, i32),
+    enabled: impl Fn(usize) -> bool,
+) -> usize {
+    if selected == 0 {
+        return 1;
+    }
+    let from = buttons[selected - 1];
+    let (dx, dy) = (direction.0 as f32, direction.1 as f32);
+    let mut best = (selected, f32::MAX);
+    for (i, &(x, y)) in buttons.iter().enumerate() {
+        let (x, y) = (x - from.0, y - from.1);
+        let along = x * dx + y * dy;
+        let across = (x * dy - y * dx).abs();
+        let distance = along + 2.0 * across;
+        if enabled(i + 1) && along > 0.0 && distance < best.1 {
+            best = (i + 1, distance);
+        }
+    }
+    best.0
+}
@@ -0,0 +1,319 @@
+use std::time::{Duration, Instant};
+
+use sdl2::controller::Axis;
+use sdl2::event::Event;
+
+use crate::graphics::mesh::Mesh;
+use crate::graphics::text::{self, Align};
+use crate::graphics::vbo::VertexBufferObject as VBO;
+use crate::graphics::vertex::Vertex;
+use crate::input::{Command, StickRepeat};
+use crate::scene::{self, Context, Scene, Transition};
+use crate::{apply_sprite, next_theme, put_rect, INK, PX, WHITE};
+
+// Top of the sheet, in pixels of the menu
+const TOP: f32 = 42.0;
+// Room for the text, which is written again whenever the selected item changes
+const TEXT_SIZE: usize = 120 * 6;
+// The tile speeds the options go through
+const TICKS_PER_SECOND: [u16; 4] = [4, 8, 12, 16];
+
+// What has been chosen in the pause menu, which the game below it does once it's closed
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PauseChoice {
+    Resume,
+    // Play the same board again from the beginning
+    RestartBoard,
+    NewGame,
+    // Go back to the menu, where the game can be continued
+    MainMenu,
+}
+
+// A sheet of paper in the middle of the window with a title and a list of items,
+// the hover mark being next to the selected one
+struct Paper {
+    mesh: Mesh,
+    title: &'static str,
+    items: Vec<String>,
+    selected: usize,
+    // Number of vertices of the sheet, the mark and the text
+    len: usize,
+    left_stick: StickRepeat,
+}
+
+impl Paper {
+    fn new(ctx: &Context, title: &'static str, items: Vec<String>) -> Result<Paper, String> {
+        let mut paper = Paper {
+            mesh: Mesh::new(&ctx.program, 12 + TEXT_SIZE)?,
+            title,
+            items,
+            selected: 0,
+            len: 0,
+            left_stick: StickRepeat::default(),
+        };
+        paper.write(ctx);
+        Ok(paper)
+    }
+
+    // Returns the top of the item `n`, in pixels of the menu
+    fn item_top(n: usize) -> f32 {
+        TOP - 20.0 - 10.0 * n as f32
+    }
+
+    // Writes the sheet, the mark and the text into the mesh
+    fn write(&mut self, ctx: &Context) {
+        let (ui, atlas) = (&ctx.theme.ui, &ctx.theme.atlas.ui);
+        let mut data: Vec<Vertex> = vec![Default::default(); 12];
+        let bottom = Paper::item_top(self.items.len()) - 6.0;
+        put_rect(
+            &mut data,
+            0,
+            PX * -49.0,
+            PX * bottom,
+            PX * 98.0,
+            PX * (TOP - bottom),
+        );
+        apply_sprite(&mut data, 0, ui, atlas.paper);
+        let y = Paper::item_top(self.selected) - 9.0;
+        put_rect(&mut data, 6, PX * -45.0, PX * y, PX * 8.99, PX * 8.99);
+        apply_sprite(&mut data, 6, ui, atlas.hover);
+
+        let font = &ctx.theme.font;
+        data.extend(font.layout(self.title, 0.0, PX * (TOP - 4.0), PX, Align::Center));
+        for (i, item) in self.items.iter().enumerate() {
+            let top = PX * Paper::item_top(i);
+            data.extend(font.layout(item, PX * -32.0, top, PX, Align::Left));
+        }
+        data.truncate(12 + TEXT_SIZE);
+        self.len = data.len();
+        self.mesh.bind();
+        VBO::write(0, &data);
+    }
+
+    // Returns the item under the pixel of the window at `x`, `y`
+    fn item_at(&self, ctx: &Context, x: i32, y: i32) -> Option<usize> {
+        let xx = (x as f32 * ctx.window_px_size.0 - 1.0 / ctx.aspect.0) / PX;
+        let yy = (1.0 / ctx.aspect.1 - y as f32 * ctx.window_px_size.1) / PX;
+        if !(-49.0..49.0).contains(&xx) {
+            return None;
+        }
+        (0..self.items.len()).find(|&i| {
+            let top = Paper::item_top(i);
+            yy <= top && yy > top - 10.0
+        })
+    }
+
+    // Moves the mark to the item `n`
+    fn select(&mut self, ctx: &mut Context, n: usize) {
+        if n != self.selected {
+            self.selected = n;
+            self.write(ctx);
+            ctx.redraw = true;
+        }
+    }
+
+    // Moves the mark with the mouse, the keys and the d-pad,
+    // returns the item chosen by `event` if there is one
+    fn handle_event(&mut self, ctx: &mut Context, event: &Event) -> Option<usize> {
+        match *event {
+            // A click only chooses the item it's on
+            Event::MouseButtonUp { x, y, .. } if ctx.bindings.confirms(event) => {
+                self.item_at(ctx, x, y)
+            }
+            _ if ctx.bindings.confirms(event) => Some(self.selected),
+            Event::MouseMotion { x, y, .. } => {
+                if let Some(n) = self.item_at(ctx, x, y) {
+                    self.select(ctx, n);
+                }
+                None
+            }
+            Event::KeyDown { .. } | Event::ControllerButtonDown { .. } => {
+                if let Some((_, dy)) = ctx.bindings.direction(event) {
+                    self.step(ctx, dy);
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    // Moves the mark with the left stick
+    fn update(&mut self, ctx: &mut Context) {
+        let stick = ctx.gamepads.stick(Axis::LeftX, Axis::LeftY);
+        if let Some((_, dy)) = self.left_stick.update(stick, Instant::now()) {
+            self.step(ctx, dy);
+        }
+    }
+
+    // Moves the mark up when `dy` is 1 and down when it's -1, going around at the ends
+    fn step(&mut self, ctx: &mut Context, dy: i32) {
+        let count = self.items.len() as i32;
+        let n = (self.selected as i32 - dy).rem_euclid(count) as usize;
+        self.select(ctx, n);
+    }
+
+    fn render(&self, ctx: &Context) {
+        self.mesh.bind();
+        ctx.view((0.0, 0.0), 1.0);
+        scene::draw(0, 12);
+        text::set_color(ctx.tint_loc, INK);
+        scene::draw(12, self.len - 12);
+        text::set_color(ctx.tint_loc, WHITE);
+    }
+}
+
+// The menu shown on top of the field while the game is paused
+pub struct PauseScene {
+    paper: Paper,
+    // Whether or not the theme can be changed in the options, the field below has to be shown again
+    themes: bool,
+}
+
+impl PauseScene {
+    pub fn new(ctx: &Context, themes: bool) -> Result<PauseScene, String> {
+        let items = [
+            "resume",
+            "restart same board",
+            "new game",
+            "options",
+            "main menu",
+        ];
+        let items = items.iter().map(|s| s.to_string()).collect();
+        Ok(PauseScene {
+            paper: Paper::new(ctx, "PAUSED", items)?,
+            themes,
+        })
+    }
+
+    // Closes the pause menu, leaving `choice` to the game
+    fn choose(ctx: &mut Context, choice: PauseChoice) -> Transition {
+        ctx.paused = Some(choice);
+        Transition::Pop
+    }
+}
+
+impl Scene for PauseScene {
+    fn handle_event(&mut self, ctx: &mut Context, event: &Event) -> Result<Transition, String> {
+        // The keys that pause resume
+        if ctx.bindings.pressed(event, Command::Pause) || ctx.bindings.pressed(event, Command::Menu)
+        {
+            return Ok(PauseScene::choose(ctx, PauseChoice::Resume));
+        }
+        Ok(match self.paper.handle_event(ctx, event) {
+            Some(0) => PauseScene::choose(ctx, PauseChoice::Resume),
+            Some(1) => PauseScene::choose(ctx, PauseChoice::RestartBoard),
+            Some(2) => PauseScene::choose(ctx, PauseChoice::NewGame),
+            Some(3) => Transition::Push(Box::new(OptionsScene::new(ctx, self.themes)?)),
+            Some(4) => PauseScene::choose(ctx, PauseChoice::MainMenu),
+            _ => Transition::Stay,
+        })
+    }
+
+    fn update(&mut self, ctx: &mut Context, _dt: Duration) -> Result<Transition, String> {
+        self.paper.update(ctx);
+        Ok(Transition::Stay)
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        self.paper.render(ctx);
+    }
+
+    fn dims(&self) -> bool {
+        true
+    }
+
+    // The theme may have been changed in the options
+    fn resumed(&mut self, ctx: &mut Context) -> Result<Transition, String> {
+        self.paper.write(ctx);
+        Ok(Transition::Stay)
+    }
+}
+
+// The settings that can be changed while playing, written back to the settings file
+// when going back to the pause menu
+pub struct OptionsScene {
+    paper: Paper,
+    changed: bool,
+    // Whether or not the theme is one of the options
+    themes: bool,
+}
+
+impl OptionsScene {
+    pub fn new(ctx: &Context, themes: bool) -> Result<OptionsScene, String> {
+        Ok(OptionsScene {
+            paper: Paper::new(ctx, "OPTIONS", OptionsScene::items(ctx, themes))?,
+            changed: false,
+            themes,
+        })
+    }
+
+    fn items(ctx: &Context, themes: bool) -> Vec<String> {
+        let game = &ctx.settings.game;
+        let mut items = vec![
+            format!("animations: {}", if game.animations { "on" } else { "off" }),
+            format!("tile speed: {}", game.ticks_per_second),
+        ];
+        if themes {
+            items.push(format!("theme: {}", game.theme));
+        }
+        items.push("back".to_string());
+        items
+    }
+
+    // Goes back to the pause menu, keeping the options if they have been changed
+    fn back(&self, ctx: &mut Context) -> Result<Transition, String> {
+        if self.changed {
+            ctx.settings.save_options(&ctx.settings_path)?;
+        }
+        Ok(Transition::Pop)
+    }
+}
+
+impl Scene for OptionsScene {
+    fn handle_event(&mut self, ctx: &mut Context, event: &Event) -> Result<Transition, String> {
+        if ctx.bindings.pressed(event, Command::Menu) {
+            return self.back(ctx);
+        }
+        let chosen = self.paper.handle_event(ctx, event);
+        let game = &mut ctx.settings.game;
+        match chosen {
+            Some(0) => game.animations = !game.animations,
+            // Go to the next speed, back to the slowest one after the fastest
+            Some(1) => {
+                game.ticks_per_second = TICKS_PER_SECOND
+                    .iter()
+                    .find(|&&ticks| ticks > game.ticks_per_second)
+                    .map_or(TICKS_PER_SECOND[0], |&ticks| ticks);
+            }
+            // Switch to the next theme, the sheet gets its textures again.
+            // The current one stays when no other can be loaded, the game goes on
+            Some(2) if self.themes => match next_theme(&ctx.settings.game.theme, &ctx.settings) {
+                Ok((name, loaded)) => {
+                    ctx.theme = loaded;
+                    ctx.settings.game.theme = name;
+                }
+                Err(_) => return Ok(Transition::Stay),
+            },
+            Some(n) if n == self.paper.items.len() - 1 => return self.back(ctx),
+            _ => return Ok(Transition::Stay),
+        }
+        self.changed = true;
+        self.paper.items = OptionsScene::items(ctx, self.themes);
+        self.paper.write(ctx);
+        ctx.redraw = true;
+        Ok(Transition::Stay)
+    }
+
+    fn update(&mut self, ctx: &mut Context, _dt: Duration) -> Result<Transition, String> {
+        self.paper.update(ctx);
+        Ok(Transition::Stay)
+    }
+
+    fn render(&mut self, ctx: &Context) {
+        self.paper.render(ctx);
+    }
+
+    fn dims(&self) -> bool {
+        true
+    }
+}
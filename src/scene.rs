@@ -0,0 +1,267 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use minesweeper::{BoardId, Difficulty, GenerationMode, MineField, Replay};
+use rand::rngs::ThreadRng;
+use sdl2::event::{Event, WindowEvent};
+
+use crate::graphics::program::Program;
+use crate::input::{Bindings, Gamepads};
+use crate::pause::PauseChoice;
+use crate::settings::Settings;
+use crate::theme::Theme;
+use crate::{DIM, WHITE};
+
+// A screen of the game. Only the scene on top of the stack gets the events and the time,
+// but they are all drawn from the bottom up, so that the ones on top can be overlays
+pub trait Scene {
+    // Reacts to `event`, closing and resizing the window are handled by the stack
+    fn handle_event(&mut self, ctx: &mut Context, event: &Event) -> Result<Transition, String>;
+
+    // Moves the scene forward by `dt`
+    fn update(&mut self, ctx: &mut Context, dt: Duration) -> Result<Transition, String>;
+
+    // Draws the scene on top of the ones below it
+    fn render(&mut self, ctx: &Context);
+
+    // Whether or not the scenes below it are drawn through the colour filter that dims them
+    fn dims(&self) -> bool {
+        false
+    }
+
+    // Gets the scene back on top once the one above it has been popped
+    fn resumed(&mut self, _ctx: &mut Context) -> Result<Transition, String> {
+        Ok(Transition::Stay)
+    }
+
+    // Adapts the scene to the new size of the window, which `ctx` already has
+    fn resized(&mut self, _ctx: &mut Context) {}
+
+    // Keeps what has to outlive the window when it gets closed
+    fn quit(&mut self, _ctx: &mut Context) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+// What the stack does with the scene on top after it has handled an event or been updated
+pub enum Transition {
+    Stay,
+    // Puts a scene on top, the others wait below it
+    Push(Box<dyn Scene>),
+    // Removes the scene on top, going back to the one below it
+    Pop,
+    // Swaps the scene on top for another one
+    Replace(Box<dyn Scene>),
+    Quit,
+}
+
+// What the scenes share: the window, the settings and the choices carried from one game to the next
+pub struct Context {
+    pub program: Program,
+    // Locations of the uniforms of the program
+    pub scale_loc: u32,
+    pub offset_loc: u32,
+    pub aspect_loc: u32,
+    pub tint_loc: u32,
+    pub filter_loc: u32,
+    // Size of the window in pixels, its aspect ratio and the size of a pixel in OpenGL space
+    pub window_size: (u32, u32),
+    pub aspect: (f32, f32),
+    pub window_px_size: (f32, f32),
+    // Whether or not the scenes have to be drawn again
+    pub redraw: bool,
+    pub settings: Settings,
+    pub settings_path: PathBuf,
+    pub theme: Theme,
+    pub bindings: Bindings,
+    pub gamepads: Gamepads,
+    pub rng: ThreadRng,
+    // Size of the last custom field
+    pub custom: Difficulty,
+    // Replay to play back instead of playing, given with '--replay <file>'
+    pub playback: Option<Replay>,
+    // Board to play first, imported with '--board <file.mbf>'
+    pub imported: Option<MineField>,
+    // Field and generation mode of the last game when it has to be played again,
+    // or of the first one when its size is given with '--width', '--height' and '--mines'
+    pub restart: Option<(Difficulty, GenerationMode)>,
+    // Seed of the first field, given with '--seed'
+    pub seed: Option<u64>,
+    // Board of the last game when it has to be played again from the pause menu
+    pub board: Option<BoardId>,
+    // What has been chosen in the pause menu, for the game below it
+    pub paused: Option<PauseChoice>,
+}
+
+impl Context {
+    // Fits the drawing to a window of `width` by `height` pixels
+    pub fn resize(&mut self, width: i32, height: i32) {
+        // Update the OpenGL viewport
+        unsafe {
+            gl::Viewport(0, 0, width, height);
+        }
+        // Calculate the new aspect ratio and pixel size
+        self.aspect = if width > height {
+            (height as f32 / width as f32, 1.0)
+        } else {
+            (1.0, width as f32 / height as f32)
+        };
+        unsafe {
+            gl::Uniform2f(self.aspect_loc as i32, self.aspect.0, self.aspect.1);
+        }
+        self.window_size = (width as u32, height as u32);
+        self.window_px_size = (
+            2.0 / (width as f32 * self.aspect.0),
+            2.0 / (height as f32 * self.aspect.1),
+        );
+        self.redraw = true;
+    }
+
+    // Multiplies the colours of what gets drawn next by `color`, white leaves them as they are
+    pub fn filter(&self, color: (f32, f32, f32, f32)) {
+        unsafe {
+            gl::Uniform4f(self.filter_loc as i32, color.0, color.1, color.2, color.3);
+        }
+    }
+
+    // Moves what gets drawn next by `offset`, then scales it by `scale`
+    pub fn view(&self, offset: (f32, f32), scale: f32) {
+        unsafe {
+            gl::Uniform2f(self.offset_loc as i32, offset.0, offset.1);
+            gl::Uniform1f(self.scale_loc as i32, scale);
+        }
+    }
+}
+
+// Draws the `count` vertices starting from `first` in the bound mesh
+pub fn draw(first: usize, count: usize) {
+    unsafe {
+        gl::DrawArrays(gl::TRIANGLES, first as i32, count as i32);
+    }
+}
+
+// The scenes being shown, the one on top being played
+pub struct Stack {
+    scenes: Vec<Box<dyn Scene>>,
+}
+
+impl Stack {
+    pub fn new(scene: Box<dyn Scene>) -> Stack {
+        Stack {
+            scenes: vec![scene],
+        }
+    }
+
+    // Gives `event` to the scene on top, returns false once the game has to stop
+    pub fn handle_event(&mut self, ctx: &mut Context, event: &Event) -> Result<bool, String> {
+        let transition = match event {
+            Event::Quit { .. } => Transition::Quit,
+            Event::Window {
+                win_event: WindowEvent::Resized(width, height),
+                ..
+            } => {
+                ctx.resize(*width, *height);
+                for scene in self.scenes.iter_mut() {
+                    scene.resized(ctx);
+                }
+                Transition::Stay
+            }
+            _ => match self.scenes.last_mut() {
+                Some(scene) => scene.handle_event(ctx, event)?,
+                None => Transition::Quit,
+            },
+        };
+        self.apply(ctx, transition)
+    }
+
+    // Moves the scene on top forward by `dt`, returns false once the game has to stop
+    pub fn update(&mut self, ctx: &mut Context, dt: Duration) -> Result<bool, String> {
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => scene.update(ctx, dt)?,
+            None => Transition::Quit,
+        };
+        self.apply(ctx, transition)
+    }
+
+    // Draws every scene from the bottom up, the ones below an overlay dimmed
+    pub fn render(&mut self, ctx: &Context) {
+        unsafe {
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+        let dimmed = self.scenes.iter().rposition(|scene| scene.dims());
+        for (i, scene) in self.scenes.iter_mut().enumerate() {
+            ctx.filter(if dimmed.is_some_and(|top| i < top) {
+                DIM
+            } else {
+                WHITE
+            });
+            scene.render(ctx);
+        }
+        ctx.filter(WHITE);
+    }
+
+    fn apply(&mut self, ctx: &mut Context, transition: Transition) -> Result<bool, String> {
+        match transition {
+            Transition::Stay => return Ok(true),
+            Transition::Push(scene) => self.scenes.push(scene),
+            Transition::Pop => {
+                self.scenes.pop();
+                if let Some(scene) = self.scenes.last_mut() {
+                    ctx.redraw = true;
+                    let transition = scene.resumed(ctx)?;
+                    return self.apply(ctx, transition);
+                }
+            }
+            Transition::Replace(scene) => {
+                self.scenes.pop();
+                self.scenes.push(scene);
+            }
+            Transition::Quit => {
+                for scene in self.scenes.iter_mut().rev() {
+                    scene.quit(ctx)?;
+                }
+                self.scenes.clear();
+            }
+        }
+        ctx.redraw = true;
+        Ok(!self.scenes.is_empty())
+    }
+}
+
+// How far an animation has gone, from 0 to 1, moved forward by the time given to the scenes
+pub struct Animation {
+    time: Duration,
+    length: Duration,
+}
+
+impl Animation {
+    // An animation lasting `length`, already over when the animations are turned off
+    pub fn new(animations: bool, length: Duration) -> Animation {
+        Animation {
+            time: if animations {
+                Duration::from_secs(0)
+            } else {
+                length
+            },
+            length,
+        }
+    }
+
+    // Moves the animation forward by `dt` and returns how far it has gone
+    pub fn advance(&mut self, dt: Duration) -> f32 {
+        self.time = (self.time + dt).min(self.length);
+        self.progress()
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.length.as_nanos() == 0 {
+            1.0
+        } else {
+            self.time.as_secs_f32() / self.length.as_secs_f32()
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.time >= self.length
+    }
+}
@@ -0,0 +1,320 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use minesweeper::{Difficulty, GenerationMode};
+use serde::{Deserialize, Serialize};
+
+use crate::theme::DEFAULT_THEME;
+
+// What can be changed without rebuilding the game, kept in a TOML file
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub window: WindowSettings,
+    pub game: GameSettings,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameSettings {
+    // Whether or not the menu slides and the field zooms in when a game starts
+    pub animations: bool,
+    // How often the tiles and the cursor move to their next frame
+    pub ticks_per_second: u16,
+    // The last choices made in the menu, which writes them back
+    pub no_guess: bool,
+    pub custom_width: usize,
+    pub custom_height: usize,
+    pub custom_mines: usize,
+    pub theme: String,
+    // Folder whose shaders and textures replace the ones built into the game
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assets: Option<PathBuf>,
+}
+
+impl Default for WindowSettings {
+    fn default() -> WindowSettings {
+        WindowSettings {
+            width: 500,
+            height: 500,
+            maximized: true,
+            fullscreen: false,
+        }
+    }
+}
+
+impl Default for GameSettings {
+    fn default() -> GameSettings {
+        let custom = Difficulty::INTERMEDIATE;
+        GameSettings {
+            animations: true,
+            ticks_per_second: 8,
+            no_guess: false,
+            custom_width: custom.width,
+            custom_height: custom.height,
+            custom_mines: custom.bombs,
+            theme: DEFAULT_THEME.to_string(),
+            assets: None,
+        }
+    }
+}
+
+impl GameSettings {
+    // How long a frame of the animated tiles lasts
+    pub fn tick_delay(&self) -> Duration {
+        Duration::from_micros(1e+6 as u64 / self.ticks_per_second.max(1) as u64)
+    }
+}
+
+impl Settings {
+    // Reads the settings in the TOML file at `path`, the ones missing from it keep their
+    // default value. When the file doesn't exist it gets created with the defaults,
+    // so that there is something to edit, a file that can't be read is left as it is
+    pub fn load(path: &Path) -> Result<Settings, String> {
+        match fs::read_to_string(path) {
+            Ok(s) => toml::from_str(&s)
+                .map_err(|e| format!("invalid settings in {}: {}", path.display(), e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let settings = Settings::default();
+                let _ = settings.save(path);
+                Ok(settings)
+            }
+            Err(e) => Err(format!(
+                "can't load the settings in {}: {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+
+    // Writes the settings to the file at `path`
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let s = toml::to_string(self).map_err(|e| format!("can't write the settings: {}", e))?;
+        let _ = path.parent().map(fs::create_dir_all);
+        fs::write(path, s).map_err(|e| format!("can't save the settings: {}", e))
+    }
+
+    // Keeps the choices made in the menu and writes them back to the file at `path`,
+    // leaving the rest of the file as it is, without what the command line overrides
+    pub fn save_menu(
+        &mut self,
+        path: &Path,
+        mode: GenerationMode,
+        custom: Difficulty,
+        theme: &str,
+    ) -> Result<(), String> {
+        let mut file = Settings::load(path)?;
+        for game in [&mut self.game, &mut file.game].iter_mut() {
+            game.no_guess = mode == GenerationMode::NoGuess;
+            game.custom_width = custom.width;
+            game.custom_height = custom.height;
+            game.custom_mines = custom.bombs;
+            game.theme = theme.to_string();
+        }
+        file.save(path)
+    }
+
+    // Writes the options chosen in the pause menu back to the file at `path`,
+    // leaving the rest of the file as it is
+    pub fn save_options(&self, path: &Path) -> Result<(), String> {
+        let mut file = Settings::load(path)?;
+        file.game.animations = self.game.animations;
+        file.game.ticks_per_second = self.game.ticks_per_second;
+        file.game.theme = self.game.theme.clone();
+        file.save(path)
+    }
+
+    // The generation mode last chosen in the menu
+    pub fn mode(&self) -> GenerationMode {
+        if self.game.no_guess {
+            GenerationMode::NoGuess
+        } else {
+            GenerationMode::Classic
+        }
+    }
+
+    // The size of the last custom field, made fit for the menu if the file has an impossible one
+    pub fn custom(&self) -> Difficulty {
+        let game = &self.game;
+        Difficulty::custom(game.custom_width, game.custom_height, game.custom_mines)
+            .unwrap_or(Difficulty::INTERMEDIATE)
+    }
+}
+
+// What the command line asks for, on top of the settings
+#[derive(Default)]
+pub struct Arguments {
+    // Size of the first field, the missing ones are taken from the last custom field
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub mines: Option<usize>,
+    // Seed of the first field generated
+    pub seed: Option<u64>,
+    pub fullscreen: bool,
+    pub no_animations: bool,
+    // Replay to play back instead of playing
+    pub replay: Option<PathBuf>,
+    // Board to play first
+    pub board: Option<PathBuf>,
+}
+
+const USAGE: &str = "usage: MineSweeper [--width <n>] [--height <n>] [--mines <n>] [--seed <n>] \
+                     [--fullscreen] [--no-animations] [--replay <file>] [--board <file.mbf>]";
+
+impl Arguments {
+    // Reads the flags in `args`, which doesn't start with the name of the program
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Arguments, String> {
+        let mut arguments = Arguments::default();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("{} needs a value\n{}", arg, USAGE))
+            };
+            match arg.as_str() {
+                "--width" => arguments.width = Some(Arguments::number(&arg, value()?)?),
+                "--height" => arguments.height = Some(Arguments::number(&arg, value()?)?),
+                "--mines" => arguments.mines = Some(Arguments::number(&arg, value()?)?),
+                "--seed" => arguments.seed = Some(Arguments::number(&arg, value()?)?),
+                "--fullscreen" => arguments.fullscreen = true,
+                "--no-animations" => arguments.no_animations = true,
+                "--replay" => arguments.replay = Some(PathBuf::from(value()?)),
+                "--board" => arguments.board = Some(PathBuf::from(value()?)),
+                _ => return Err(format!("unknown argument '{}'\n{}", arg, USAGE)),
+            }
+        }
+        Ok(arguments)
+    }
+
+    // Returns the value `s` of the flag `arg` as a number
+    fn number<T: FromStr>(arg: &str, s: String) -> Result<T, String> {
+        s.parse()
+            .map_err(|_| format!("{} needs a number, not '{}'\n{}", arg, s, USAGE))
+    }
+
+    // Puts what the command line overrides in `settings`
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.window.fullscreen |= self.fullscreen;
+        settings.game.animations &= !self.no_animations;
+    }
+
+    // The field to play first when its size is given, the sizes not given are the custom ones
+    pub fn difficulty(&self, custom: Difficulty) -> Result<Option<Difficulty>, String> {
+        if self.width.is_none() && self.height.is_none() && self.mines.is_none() {
+            return Ok(None);
+        }
+        Difficulty::custom(
+            self.width.unwrap_or(custom.width),
+            self.height.unwrap_or(custom.height),
+            self.mines.unwrap_or(custom.bombs),
+        )
+        .map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Arguments, String> {
+        Arguments::parse(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn arguments_are_read() {
+        let arguments = parse(&[
+            "--width",
+            "20",
+            "--mines",
+            "50",
+            "--seed",
+            "7",
+            "--fullscreen",
+            "--replay",
+            "a.rmv",
+        ])
+        .unwrap();
+        assert_eq!(arguments.width, Some(20));
+        assert_eq!(arguments.height, None);
+        assert_eq!(arguments.mines, Some(50));
+        assert_eq!(arguments.seed, Some(7));
+        assert!(arguments.fullscreen && !arguments.no_animations);
+        assert_eq!(arguments.replay, Some(PathBuf::from("a.rmv")));
+        assert_eq!(arguments.board, None);
+    }
+
+    #[test]
+    fn command_line_overrides_the_file() {
+        let file = "[window]\nfullscreen = false\n[game]\nanimations = true\ncustom_height = 12\n";
+        let mut settings: Settings = toml::from_str(file).unwrap();
+        let arguments = parse(&["--fullscreen", "--no-animations", "--width", "10"]).unwrap();
+        arguments.apply(&mut settings);
+        assert!(settings.window.fullscreen);
+        assert!(!settings.game.animations);
+        // The sizes missing from the command line are the custom ones of the file
+        let custom = Difficulty::custom(9, 12, 20).unwrap();
+        assert_eq!(
+            arguments.difficulty(custom),
+            Ok(Some(Difficulty::custom(10, 12, 20).unwrap()))
+        );
+
+        // Without flags the file is kept as it is
+        let file = "[window]\nfullscreen = true\n[game]\nanimations = false\n";
+        let mut settings: Settings = toml::from_str(file).unwrap();
+        let arguments = parse(&[]).unwrap();
+        arguments.apply(&mut settings);
+        assert!(settings.window.fullscreen);
+        assert!(!settings.game.animations);
+        assert_eq!(arguments.difficulty(custom), Ok(None));
+    }
+
+    #[test]
+    fn unreadable_settings_are_not_replaced() {
+        // A folder can't be read as a file, but it isn't missing either
+        let dir = std::env::temp_dir();
+        let error = Settings::load(&dir).err().unwrap();
+        assert!(error.starts_with("can't load the settings"));
+        assert!(dir.is_dir());
+    }
+
+    #[test]
+    fn wrong_arguments_are_refused() {
+        let error = |args: &[&str]| parse(args).err().unwrap();
+        assert_eq!(
+            error(&["--fullscreen", "--big"]),
+            format!("unknown argument '--big'\n{}", USAGE)
+        );
+        assert_eq!(
+            error(&["--width"]),
+            format!("--width needs a value\n{}", USAGE)
+        );
+        assert_eq!(
+            error(&["--board"]),
+            format!("--board needs a value\n{}", USAGE)
+        );
+        assert_eq!(
+            error(&["--seed", "-3"]),
+            format!("--seed needs a number, not '-3'\n{}", USAGE)
+        );
+        // The sizes are read as they are, never cut down to fit
+        let big = format!("{}0", usize::MAX);
+        assert_eq!(
+            error(&["--mines", &big]),
+            format!("--mines needs a number, not '{}'\n{}", big, USAGE)
+        );
+        // Impossible fields are refused once the custom sizes are known
+        let arguments = parse(&["--height", "2"]).unwrap();
+        assert!(arguments.difficulty(Difficulty::BEGINNER).is_err());
+    }
+}
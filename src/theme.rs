@@ -0,0 +1,270 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::assets::Assets;
+use crate::graphics::text::Font;
+use crate::graphics::texture::Texture;
+use crate::{FONT_TEXTURE, GROUND_TEXTURE, PROPS_TEXTURE, UI_TEXTURE};
+
+// The theme made of the files built into the game
+pub const DEFAULT_THEME: &str = "default";
+// Name of the atlas manifest in the assets folder of a theme
+const ATLAS_FILE: &str = "textures/atlas.toml";
+
+// A region of a texture in pixels from its top left corner: x, y, width and height
+pub type Region = [f32; 4];
+
+// Where the sprites are in the textures of a theme, read from its atlas manifest
+#[derive(Deserialize)]
+pub struct Atlas {
+    pub ground: GroundAtlas,
+    pub props: PropsAtlas,
+    pub ui: UiAtlas,
+    pub font: FontAtlas,
+}
+
+// The ground is made of columns of tiles, whose rows are the frames of their animation
+#[derive(Deserialize)]
+pub struct GroundAtlas {
+    pub texture: String,
+    pub tile: (f32, f32),
+    // The sand under the grass, its rows are variations
+    pub sand: u32,
+    // Border piece 1 of the digged tiles, piece n is n - 1 columns after it
+    pub borders: u32,
+    // The frame around the field
+    pub left: u32,
+    pub bottom: u32,
+    pub right: u32,
+    pub top: u32,
+    pub lower_left: u32,
+    pub upper_left: u32,
+    pub upper_right: u32,
+    pub lower_right: u32,
+}
+
+// The props are made of columns of tiles, whose rows are variations or the frames of an animation
+#[derive(Deserialize)]
+pub struct PropsAtlas {
+    pub texture: String,
+    pub tile: (f32, f32),
+    pub grass: u32,
+    // The digged tile without bombs near, the ones with 1 to 8 bombs near follow
+    pub numbers: u32,
+    // The digged bomb, with its variations in the next columns
+    pub bomb: u32,
+    // The two stages of the explosion after the bomb, and the row of the second one
+    // left once it's over
+    pub explosion: (u32, u32),
+    pub crater: u32,
+    pub flag: u32,
+    // The bombs shown when the game is lost, with and without a flag on top
+    pub flagged_bomb: u32,
+    pub hidden_bomb: u32,
+}
+
+#[derive(Deserialize)]
+pub struct UiAtlas {
+    pub texture: String,
+    // Size of the frames of the cursors
+    pub tile: (f32, f32),
+    // The cursors on the selected tile and on the pointed one
+    pub selected: Region,
+    pub cursor: Region,
+    // The contract of the menu, which is also the page of the results
+    pub menu: Region,
+    // The roll at the bottom of the contract, lying on its side
+    pub roll: Region,
+    // The mark on the hovered button and the one on the chosen boxes
+    pub hover: Region,
+    pub check: Region,
+    pub continue_label: Region,
+    // A spot of plain paper, stretched to hide the contract
+    pub paper: Region,
+    pub custom_panel: Region,
+    // The next button shown after a bomb, and the edge of the page hiding it
+    pub next: Region,
+    pub page_edge: Region,
+    // The words at the bottom of the page of the results, lying on their side
+    pub won: Region,
+    pub lost: Region,
+    // The digit 0, the others follow `digit_step` pixels apart
+    pub digits: Region,
+    pub digit_step: f32,
+    pub minus: Region,
+    pub hud_panel: Region,
+    pub mines_icon: Region,
+    pub clock_icon: Region,
+    // The face while playing, then the winning and the losing ones, `face_step` pixels apart
+    pub faces: Region,
+    pub face_step: f32,
+}
+
+#[derive(Deserialize)]
+pub struct FontAtlas {
+    pub texture: String,
+    pub cell: (usize, usize),
+}
+
+impl GroundAtlas {
+    // Returns the column of a tile whose border is `border`, the sand when it isn't digged
+    pub fn border(&self, border: u32) -> u32 {
+        if border == 0 {
+            self.sand
+        } else {
+            self.borders + border - 1
+        }
+    }
+}
+
+impl PropsAtlas {
+    // Returns the column of a digged tile with `near` bombs near
+    pub fn number(&self, near: u8) -> u32 {
+        self.numbers + near as u32
+    }
+}
+
+impl UiAtlas {
+    // Returns the region of `digit`
+    pub fn digit(&self, digit: usize) -> Region {
+        let [x, y, w, h] = self.digits;
+        [x + self.digit_step * digit as f32, y, w, h]
+    }
+
+    // Returns the region of the face `n`: playing, won or lost
+    pub fn face(&self, n: usize) -> Region {
+        let [x, y, w, h] = self.faces;
+        [x + self.face_step * n as f32, y, w, h]
+    }
+}
+
+impl Atlas {
+    // Reads the atlas manifest of the theme whose files are in `assets`
+    pub fn load(assets: &Assets) -> Result<Atlas, String> {
+        toml::from_str(&assets.load_string(ATLAS_FILE)?)
+            .map_err(|e| format!("invalid atlas {}: {}", ATLAS_FILE, e))
+    }
+}
+
+// A texture bound to its texture unit, with the sizes to turn the regions of the atlas
+// into texture coordinates
+#[derive(Clone, Copy)]
+pub struct Sheet {
+    pub unit: i32,
+    // Size of a pixel and of a tile in texture coordinates
+    pub px: (f32, f32),
+    pub tile: (f32, f32),
+}
+
+impl Sheet {
+    fn new(texture: &Texture, unit: u32, tile: (f32, f32)) -> Sheet {
+        let px = texture.pixel_size();
+        Sheet {
+            unit: unit as i32,
+            px,
+            tile: (px.0 * tile.0, px.1 * tile.1),
+        }
+    }
+
+    // Returns the texture coordinates of `region`: x, y, width and height
+    pub fn rect(&self, region: Region) -> (f32, f32, f32, f32) {
+        let [x, y, w, h] = region;
+        (x * self.px.0, y * self.px.1, w * self.px.0, h * self.px.1)
+    }
+
+    // Returns where the column of tiles `column` is and its width
+    pub fn column(&self, column: u32) -> (f32, f32) {
+        (column as f32 * self.tile.0, self.tile.0)
+    }
+
+    // Returns where the row of tiles `row` is and its height
+    pub fn row(&self, row: u32) -> (f32, f32) {
+        (row as f32 * self.tile.1, self.tile.1)
+    }
+}
+
+// The textures and the atlas of a theme, loaded into their texture units
+pub struct Theme {
+    pub atlas: Atlas,
+    pub ground: Sheet,
+    pub props: Sheet,
+    pub ui: Sheet,
+    pub font: Font,
+    // Kept alive while they are bound to their units
+    _textures: Vec<Texture>,
+}
+
+impl Theme {
+    // Loads the theme whose files are in `assets`. Everything is read before the textures
+    // are replaced, so that a broken theme leaves the current one as it is
+    pub fn load(assets: &Assets) -> Result<Theme, String> {
+        let atlas = Atlas::load(assets)?;
+        let image = |name: &str| {
+            let name = format!("textures/{}", name);
+            Texture::decode(&assets.load(&name)?, &name)
+        };
+        let ground = image(&atlas.ground.texture)?;
+        let props = image(&atlas.props.texture)?;
+        let ui = image(&atlas.ui.texture)?;
+        let font = image(&atlas.font.texture)?;
+
+        // The font is the only one that can still be refused, so it goes first
+        Texture::set_active_unit(FONT_TEXTURE);
+        let font = Font::from_image(
+            &font,
+            &atlas.font.texture,
+            atlas.font.cell,
+            FONT_TEXTURE as i32,
+        )?;
+        let mut textures = Vec::new();
+        let mut sheets = Vec::new();
+        for (img, unit, tile) in [
+            (&ground, GROUND_TEXTURE, atlas.ground.tile),
+            (&props, PROPS_TEXTURE, atlas.props.tile),
+            (&ui, UI_TEXTURE, atlas.ui.tile),
+        ]
+        .iter()
+        {
+            Texture::set_active_unit(*unit);
+            let texture = Texture::from_image(img);
+            sheets.push(Sheet::new(&texture, *unit, *tile));
+            textures.push(texture);
+        }
+        Ok(Theme {
+            ground: sheets[0],
+            props: sheets[1],
+            ui: sheets[2],
+            atlas,
+            font,
+            _textures: textures,
+        })
+    }
+}
+
+// Returns the names of the themes, the default first and then the folders in `dir`
+pub fn themes(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| name != DEFAULT_THEME)
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names.insert(0, DEFAULT_THEME.to_string());
+    names
+}
+
+// Returns the folder of the theme `name` among the ones in `dir`, none for the default one
+pub fn theme_dir(dir: &Path, name: &str) -> Option<PathBuf> {
+    if name == DEFAULT_THEME {
+        None
+    } else {
+        Some(dir.join(name))
+    }
+}